@@ -1,35 +1,415 @@
-use std::io::{self, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 use uuid::Uuid;
 use chrono::prelude::*;
+use rustyline::error::ReadlineError;
 use std::collections::{BTreeMap, HashMap};
-use crate::graph::{EntityType, RelationshipType, Entity, Relationship};
+use serde::{Deserialize, Serialize};
+use crate::graph::{EntityType, RelationshipType, Entity, Relationship, ALIASES_KEY};
 use crate::graph::fact::{Fact, FactStore};
-use crate::graph::GraphDb;
-use crate::engine::case::{display_case, CaseBuilder};
+use crate::graph::{GraphDb, LifecycleSummary};
+use crate::engine::case::{display_case, format_fact, Case, CaseBuilder};
+use crate::engine::search::{parse_query, search_entities, SearchQuery};
+use crate::engine::compare::compare_entities;
+use crate::engine::timeline::{generate_timeline, TimelineQuery};
+use crate::engine::utils::parse_date;
+use crate::io::FactTail;
+use crate::cli::output::Output;
 use crate::cli::utils;
 use crate::cli::utils::{CYAN, GREEN, MAGENTA, RED, RESET, YELLOW};
 
+/// State carried from one command to the next within a single REPL session.
+pub struct Session {
+    pub db: GraphDb,                            // The live graph the commands operate on
+    pub data_file: String,                      // File used by `save` and `load`
+    pub reciprocals: HashMap<String, String>,   // Opt-in reverse edges, e.g. WorksAt -> Employs
+    pub output: Output,                         // Human or JSON rendering of command results
+    pub relative_times: bool,                   // Show "3 days ago" instead of absolute timestamps
+    pub unique_names: bool,                     // Refuse to add an entity whose name (ignoring case) is taken
+    pub rotations: usize,                       // Numbered backups kept by `save` (0 = overwrite in place)
+    pub last_case: Option<Case>,                // Most recent `build-case` result, explored by `why-fact`
+    pub focus: Option<Uuid>,                    // Entity that `facts`, `expand` and `build-case` use when no name is given
+    pub label_key: Option<String>,              // Property shown instead of the entity name wherever entities are displayed (falls back to the name)
+    pub pending_delete: Option<Vec<Uuid>>,      // Matches of a `delete-query` awaiting the user's y/N answer
+    pub errors: usize,                          // Commands that have reported an error so far (script mode's exit status)
+    pub scripted: bool,                         // Running a `--script` file: nothing prompts, and `save`/`load` need a file name
+}
+
+impl Session {
+    pub fn new(db: GraphDb, data_file: &str) -> Self {
+        Session {
+            db,
+            data_file: data_file.to_string(),
+            reciprocals: HashMap::new(),
+            output: Output::human(),
+            relative_times: false,
+            unique_names: false,
+            rotations: 0,
+            last_case: None,
+            focus: None,
+            label_key: None,
+            pending_delete: None,
+            errors: 0,
+            scripted: false,
+        }
+    }
+
+    /// A session for `--script` mode: a fresh graph with no data file of its own, so a script
+    /// can't overwrite the user's saved graph by accident.
+    pub fn for_script() -> Self {
+        Session { scripted: true, ..Session::new(GraphDb::new(), "") }
+    }
+
+    /// Reports a failed command through the active output mode and counts it in `errors`.
+    pub fn error(&mut self, out: &mut dyn Write, message: &str) -> io::Result<()> {
+        self.errors += 1;
+        self.output.error(out, message)
+    }
+}
+
+/// Summary counts reported by the `stats` command.
+#[derive(Debug, Serialize)]
+struct GraphStats {
+    entities: usize,
+    relationships: usize,
+    facts: usize,
+    lifecycle: LifecycleSummary,
+    most_connected: Vec<(Uuid, usize)>,
+}
+
+/// Compact `{id, name, type}` form of an entity, emitted in JSON mode by `add-entity`, `search` and `query`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct EntitySummary {
+    id: Uuid,
+    name: String,
+    #[serde(rename = "type")]
+    entity_type: String,
+}
+
+impl EntitySummary {
+    fn of(entity: &Entity) -> Self {
+        EntitySummary { id: entity.id, name: entity.name.clone(), entity_type: entity.entity_type.to_string() }
+    }
+}
+
+/// Resolves an entity argument typed at the REPL. A full UUID is looked up directly, which stays
+/// unambiguous when names collide; anything else is matched as a name ignoring case (see `GraphDb::find_by_name_ci`).
 fn find_entity_by_name<'a>(db: &'a GraphDb, name: &str) -> Option<&'a Entity> {
-    db.graph.node_weights().find(|e| e.name == name)
+    match Uuid::from_str(name) {
+        Ok(id) => db.get_entity(&id),
+        Err(_) => db.find_by_name_ci(name),
+    }
+}
+
+/// Splits a command line on whitespace, treating `"..."` as a single argument (quotes removed).
+/// An unterminated quote runs to the end of the line.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    args
+}
+
+/// Writes the candidates when `name` matches more than one entity, so the user can pick one by UUID.
+/// Returns `true` when the name was ambiguous and the command should stop.
+fn report_ambiguous(session: &Session, name: &str, out: &mut dyn Write) -> io::Result<bool> {
+    let matches = session.db.find_all_by_name(name);
+    if matches.len() < 2 {
+        return Ok(false);
+    }
+    writeln!(out, "{}Several entities are named '{}'; paste one of these UUIDs in place of the name:{}", YELLOW, name, RESET)?;
+    writeln!(out, "{}", format_entity_list(&matches, session.label_key.as_deref()))?;
+    Ok(true)
+}
+
+/// Writes "Did you mean ...?" with up to three entity names close to a name that wasn't found.
+fn suggest_names(db: &GraphDb, name: &str, out: &mut dyn Write) -> io::Result<()> {
+    let suggestions: Vec<String> = db.fuzzy_find(name, 3)
+        .into_iter()
+        .take(3)
+        .map(|(e, _)| format!("'{}'", e.name))
+        .collect();
+    if !suggestions.is_empty() {
+        writeln!(out, "{}Did you mean {}?{}", YELLOW, suggestions.join(" or "), RESET)?;
+    }
+    Ok(())
+}
+
+/// Resolves a command's entity argument, falling back to the session focus when it is omitted.
+fn entity_or_focus<'a>(session: &'a Session, name: Option<&str>) -> Option<&'a Entity> {
+    match name {
+        Some(name) => find_entity_by_name(&session.db, name),
+        None => session.focus.and_then(|id| session.db.get_entity(&id)),
+    }
+}
+
+/// The file `save` or `load` works on: the one named in `args`, else the session's data file.
+/// A script session has no data file, so there the name is required.
+fn save_file(session: &Session, args: &[&str]) -> Option<String> {
+    match args.first() {
+        Some(file) => Some(file.to_string()),
+        None if session.scripted => None,
+        None => Some(session.data_file.clone()),
+    }
+}
+
+/// Runs a `query` command: `type:<EntityType>` and/or `name:<text>` filters (bare words count as name text),
+/// in the order a `sort:` token asks for.
+fn query_results<'a>(db: &'a GraphDb, args: &[&str]) -> Result<Vec<&'a Entity>, String> {
+    Ok(search_entities(db, parse_query(&args.join(" "))?))
+}
+
+/// Builds the `SearchQuery` for `search <substring>` (words joined with spaces) or `search-type <EntityType>`.
+fn simple_search_query(command: &str, args: &[&str]) -> Result<SearchQuery, String> {
+    match command {
+        "search" if !args.is_empty() => Ok(SearchQuery { name_contains: Some(args.join(" ")), ..Default::default() }),
+        "search-type" if args.len() == 1 => {
+            let etype = EntityType::from_str(args[0]).map_err(|_| format!("Unknown entity type: {}", args[0]))?;
+            Ok(SearchQuery { entity_type: Some(etype), ..Default::default() })
+        }
+        "search-type" => Err("Usage: search-type <entity_type>".to_string()),
+        _ => Err("Usage: search <substring>".to_string()),
+    }
+}
+
+/// Human rendering of an entity list: a numbered list of `label (type) [uuid]`, labelled by `label_key` when given.
+fn format_entity_list(entities: &[&Entity], label_key: Option<&str>) -> String {
+    if entities.is_empty() {
+        return format!("{}No matching entities.{}", YELLOW, RESET);
+    }
+    entities.iter()
+        .enumerate()
+        .map(|(i, e)| format!("  {}. {} ({}) [{}]", i + 1, e.display_name(label_key), e.entity_type.to_string(), e.id))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Default save file, used unless a path is passed or H3IMD3LL_DATA is set.
+const DEFAULT_DATA_FILE: &str = "graph_data.json";
+const DATA_FILE_ENV: &str = "H3IMD3LL_DATA";
+
+// Interactive command history, kept in the home directory and capped to the most recent entries.
+const HISTORY_FILE: &str = ".h3imd3ll_history";
+const HISTORY_LIMIT: usize = 1000;
+
+/// Where the REPL keeps its command history: `~/.h3imd3ll_history`, or None without a home directory.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+/// Reads a history file, one command per line, oldest first. A missing file is an empty history.
+fn read_history(path: &Path) -> io::Result<Vec<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.lines().filter(|l| !l.trim().is_empty()).map(String::from).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the last `HISTORY_LIMIT` entries of `entries` to a history file, one per line.
+fn write_history(path: &Path, entries: &[String]) -> io::Result<()> {
+    let start = entries.len().saturating_sub(HISTORY_LIMIT);
+    let mut contents = String::new();
+    for entry in &entries[start..] {
+        contents.push_str(entry);
+        contents.push('\n');
+    }
+    std::fs::write(path, contents)
 }
 
-pub fn run_h3imd3ll_repl() -> io::Result<()> {
+/// Starts the REPL on the file named by `H3IMD3LL_DATA`, or `graph_data.json` when it isn't set.
+pub fn run_h3imd3ll_repl(output: Output) -> io::Result<()> {
+    let path = std::env::var_os(DATA_FILE_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_FILE));
+    run_h3imd3ll_repl_with_path(&path, output)
+}
+
+/// Starts the REPL on stdin/stdout, loading from and saving to `path` and rendering with `output`.
+/// On a terminal, lines are read with line editing and history, restored from and saved to
+/// `~/.h3imd3ll_history`; piped input and `--json` mode read plain lines.
+pub fn run_h3imd3ll_repl_with_path(path: &Path, output: Output) -> io::Result<()> {
+    if output.is_json() || !io::stdin().is_terminal() {
+        return run_repl(path, output, io::stdin().lock(), &mut io::stdout());
+    }
+
+    let mut editor = rustyline::DefaultEditor::new().map_err(io::Error::other)?;
+    let history_file = history_path();
+    let mut history = match &history_file {
+        Some(file) => read_history(file).unwrap_or_else(|e| {
+            eprintln!("Failed to read history from {}: {}", file.display(), e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    for entry in &history {
+        let _ = editor.add_history_entry(entry.as_str());
+    }
+
+    let result = repl_loop(path, output, &mut io::stdout(), |_, prompt| match editor.readline(prompt) {
+        Ok(line) => {
+            if !line.trim().is_empty() && history.last() != Some(&line) {
+                let _ = editor.add_history_entry(line.as_str());
+                history.push(line.clone());
+            }
+            Ok(Some(line))
+        }
+        // Ctrl+D and Ctrl+C both leave the REPL, like EOF on plain input
+        Err(ReadlineError::Eof | ReadlineError::Interrupted) => Ok(None),
+        Err(ReadlineError::Io(e)) => Err(e),
+        Err(e) => Err(io::Error::other(e)),
+    });
+
+    if let Some(file) = &history_file {
+        write_history(file, &history)
+            .unwrap_or_else(|e| eprintln!("Failed to save history to {}: {}", file.display(), e));
+    }
+    result
+}
+
+/// Runs the REPL commands in the file at `path`, one per line, against a fresh graph and prints
+/// their results to stdout. Blank lines and lines starting with `#` are skipped; `exit` stops early.
+/// `save` and `load` need an explicit file, and `delete-query` needs `--yes`, since nobody is there to answer.
+/// Returns the process exit code: 0 if every command succeeded, 1 if any reported an error.
+pub fn run_h3imd3ll_script(path: &Path, output: Output) -> io::Result<i32> {
+    let script = io::BufReader::new(std::fs::File::open(path)?);
+    let mut session = Session::for_script();
+    session.output = output;
+    run_script(&mut session, script, &mut io::stdout())?;
+    Ok(if session.errors == 0 { 0 } else { 1 })
+}
+
+/// Executes each command line of `script` against `session`, echoing it first in human mode.
+fn run_script(session: &mut Session, script: impl BufRead, out: &mut dyn Write) -> io::Result<()> {
+    for line in script.lines() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !session.output.is_json() {
+            utils::with_colors(session.output.colors(), out, |out| writeln!(out, "{}> {}{}", CYAN, trimmed, RESET))?;
+        }
+        if !execute_command(session, trimmed, out)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The REPL loop itself: reads command lines from `input` until EOF or `exit`, writing everything to `out`.
+fn run_repl(path: &Path, output: Output, mut input: impl BufRead, out: &mut dyn Write) -> io::Result<()> {
+    let mut line = String::new();
+    repl_loop(path, output, out, |out, prompt| {
+        if !prompt.is_empty() {
+            write!(out, "{}", prompt)?;
+            out.flush()?;  // Make sure prompt is printed
+        }
+        line.clear();
+        Ok(match input.read_line(&mut line)? {
+            0 => None,
+            _ => Some(line.clone()),
+        })
+    })
+}
+
+/// Loads the graph at `path`, prints the banner and executes lines from `next_line` until it
+/// returns None (EOF) or `exit`. `next_line` is given the prompt to show, empty in JSON mode.
+fn repl_loop(
+    path: &Path,
+    output: Output,
+    out: &mut dyn Write,
+    mut next_line: impl FnMut(&mut dyn Write, &str) -> io::Result<Option<String>>,
+) -> io::Result<()> {
     let mut db = GraphDb::new();
-    let data_file = "graph_data.json";
+    let data_file = path.to_string_lossy();
+
+    utils::with_colors(output.colors(), out, |out| {
+        // Load existing data if any
+        if path.exists() {
+            match GraphDb::load_from_file(&data_file) {
+                Ok(loaded_db) => {
+                    db = loaded_db;
+                    if !output.is_json() {
+                        writeln!(out, "Loaded graph from {}", data_file)?;
+                    }
+                    for warning in &db.load_warnings {
+                        output.warning(out, warning)?;
+                    }
+                }
+                Err(e) => output.error(out, &format!("Failed to load graph from file: {}", e))?,
+            }
+        }
+        if !output.is_json() {
+            writeln!(out)?;
+            print_banner(out)?;
+        }
+        Ok::<_, io::Error>(())
+    })?;
+
+    let mut session = Session::new(db, &data_file);
+    session.output = output;
 
-    // Load existing data if any
-    if std::path::Path::new(data_file).exists() {
-        match GraphDb::load_from_file(data_file) {
-            Ok(loaded_db) => {
-                db = loaded_db;
-                println!("Loaded graph from {}", data_file);
+    loop {
+        // `set color` can change the prompt from one line to the next
+        let prompt = match (session.output.is_json(), session.output.colors()) {
+            (true, _) => String::new(),
+            (false, true) => format!("{}🔍 h3imd3ll> {} ", CYAN, RESET),
+            (false, false) => "🔍 h3imd3ll> ".to_string(),
+        };
+        let Some(line) = next_line(out, &prompt)? else {
+            // EOF (Ctrl+D)
+            if !session.output.is_json() {
+                writeln!(out, "\nExiting...")?;
             }
-            Err(e) => println!("Failed to load graph from file: {}", e),
+            break;
+        };
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() && session.pending_delete.is_none() {
+            continue; // ignore empty lines, unless they answer a delete prompt
+        }
+
+        if !execute_command(&mut session, trimmed, out)? {
+            break;
         }
     }
-    println!();
-    println!(
+
+    Ok(())
+}
+
+fn print_banner(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
         "{}{}{}",
         CYAN,
         r#"
@@ -44,173 +424,1020 @@ pub fn run_h3imd3ll_repl() -> io::Result<()> {
                            *************************************************************************
         "#,
         RESET,
-    );
+    )
+}
 
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut input = String::new();
+static WATCHING: AtomicBool = AtomicBool::new(false);           // A `watch` is running
+static WATCH_INTERRUPTED: AtomicBool = AtomicBool::new(false);  // Ctrl+C was pressed during it
 
-    loop {
-        input.clear();
-        print!("{}🔍 h3imd3ll> {} ", CYAN, RESET);
-        stdout.flush()?;  // Make sure prompt is printed
+/// Installs the Ctrl+C handler that stops `watch`, once per process. Returns false if it couldn't be installed.
+/// Outside a watch, Ctrl+C still ends the program.
+fn catch_watch_interrupts() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        ctrlc::set_handler(|| {
+            if WATCHING.load(Ordering::SeqCst) {
+                WATCH_INTERRUPTED.store(true, Ordering::SeqCst);
+            } else {
+                std::process::exit(130);
+            }
+        })
+        .is_ok()
+    })
+}
 
-        if stdin.read_line(&mut input)? == 0 {
-            // EOF (Ctrl+D)
-            println!("\nExiting...");
-            break;
+/// Parses and runs a single REPL command line against the session.
+/// All output goes to `out`, so commands can be captured in tests or redirected; with the
+/// session's colors off, it is written without escape codes.
+/// Returns `false` when the session should end (`exit`/`quit`), `true` otherwise.
+pub fn execute_command(session: &mut Session, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+    let colors = session.output.colors();
+    utils::with_colors(colors, out, |out| run_command(session, line, out))
+}
+
+fn run_command(session: &mut Session, line: &str, out: &mut dyn Write) -> io::Result<bool> {
+    // A pending `delete-query` takes this line as the answer to its prompt; anything else
+    // cancels it and runs as a command
+    if let Some(ids) = session.pending_delete.take() {
+        match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => {
+                match session.db.delete_entities(&ids) {
+                    Ok(deleted) => writeln!(out, "{}Deleted {} entities.{}", GREEN, deleted, RESET)?,
+                    Err(e) => session.error(out, &format!("Nothing deleted: {}", e))?,
+                }
+                return Ok(true);
+            }
+            "n" | "no" | "" => {
+                writeln!(out, "{}Deletion cancelled.{}", YELLOW, RESET)?;
+                return Ok(true);
+            }
+            _ => writeln!(out, "{}Deletion cancelled: not a y/N answer.{}", YELLOW, RESET)?,
         }
+    }
+
+    // Split input into command and args; double quotes keep multi-word values together
+    let tokens = split_args(line);
+    let Some((cmd, rest)) = tokens.split_first() else {
+        return Ok(true);
+    };
+    let args: Vec<&str> = rest.iter().map(String::as_str).collect();
+
+    match cmd.to_lowercase().as_str() {
+        "add-entity" => {
+            if args.len() < 2 {
+                session.error(out, "Usage: add-entity <name> <entity_type>")?;
+                return Ok(true);
+            }
+            let name = args[0];
+            let entity_type_str = args[1];
+            if let Some(existing) = session.db.entities_named(name).first().filter(|_| session.unique_names) {
+                let message = format!("An entity named '{}' already exists with ID {} (unique-names is on).", existing.name, existing.id);
+                session.error(out, &message)?;
+                return Ok(true);
+            }
+            match EntityType::from_str(entity_type_str) {
+                Ok(etype) => {
+                    let entity_id = Uuid::new_v4();
+
+                    // Refuse names that can't be what the type says (e.g. an Email without an '@')
+                    let candidate = Entity { id: entity_id, name: name.to_string(), entity_type: etype, properties: BTreeMap::new(), aliases: Vec::new() };
+                    if let Err(e) = candidate.validate() {
+                        session.error(out, &format!("Invalid entity: {}", e))?;
+                        return Ok(true);
+                    }
+                    let summary = EntitySummary::of(&candidate);
+
+                    // Build properties map with required keys
+                    let mut properties = BTreeMap::new();
+                    properties.insert("name".to_string(), name.to_string());
+                    properties.insert("type".to_string(), entity_type_str.to_string());
+
+                    // Create the fact store with EntityCreated fact carrying these
+                    let fact_store = FactStore {
+                        facts: vec![Fact::EntityCreated {
+                            entity_id,
+                            timestamp: Utc::now(),
+                            properties,
+                        }]
+                    };
+                    match session.db.add_facts_atomic(fact_store) {
+                        Ok(()) => session.output.emit(out, &summary, |s| format!("{}Entity '{}' added with ID {}{}", GREEN, s.name, s.id, RESET))?,
+                        Err(e) => session.error(out, &format!("Entity not added: {}", e))?,
+                    }
+                }
+                Err(_) => {
+                    session.error(out, &format!("Invalid entity type: {}", entity_type_str))?;
+                }
+            }
+        }
+        "add-fact" => {
+            if args.len() < 3 {
+                session.error(out, "Usage: add-fact <subject> <predicate> <object>")?;
+                return Ok(true);
+            }
+            let subject = args[0];
+            let predicate = args[1];
+            let object = args[2];
+
+            if report_ambiguous(session, subject, out)? || report_ambiguous(session, object, out)? {
+                return Ok(true);
+            }
+
+            let subject_id = find_entity_by_name(&session.db, subject).map(|e| e.id);
+            let object_id = find_entity_by_name(&session.db, object).map(|e| e.id);
 
-        let trimmed = input.trim();
-        if trimmed.is_empty() {
-            continue; // ignore empty lines
-        }
-
-        // Split input into command and args
-        let mut parts = trimmed.split_whitespace();
-        let cmd = parts.next().unwrap();
-        let args: Vec<&str> = parts.collect();
-
-        match cmd.to_lowercase().as_str() {
-            "add-entity" => {
-                if args.len() < 2 {
-                    println!("{}Usage: add-entity <name> <entity_type> {}", GREEN, RESET);
-                    continue;
-                }
-                let name = args[0];
-                let entity_type_str = args[1];
-                match EntityType::from_str(entity_type_str) {
-                    Ok(etype) => {
-                        let entity_id = Uuid::new_v4();
-                        
-                        // Build properties map with required keys
-                        let mut properties = BTreeMap::new();
-                        properties.insert("name".to_string(), name.to_string());
-                        properties.insert("type".to_string(), entity_type_str.to_string());
-                        
-                        // Create the fact store with EntityCreated fact carrying these
-                        let fact_store = FactStore {
-                            facts: vec![Fact::EntityCreated {
-                                entity_id,
-                                timestamp: Local::now(),
-                                properties,
-                            }]
-                        };
-                        db.add_fact(fact_store);
-                        println!("{}Entity '{}' added with ID {}{}", GREEN, name, entity_id, RESET);
-                    }
-                    Err(_) => {
-                        println!("{}Invalid entity type: {}{}", RED, entity_type_str, RESET);
-                    }
-                }
-            }
-            "add-fact" => {
-                if args.len() < 3 {
-                    println!("{}Usage: add-fact <subject> <predicate> <object> {}", GREEN, RESET);
-                    continue;
-                }
-                let subject = args[0];
-                let predicate = args[1];
-                let object = args[2];
-
-                let subject_entity = find_entity_by_name(&db, subject);
-                let object_entity = find_entity_by_name(&db, object);
-
-                if subject_entity.is_none() || object_entity.is_none() {
-                    println!("Subject or object entity not found.");
-                    continue;
-                }
-                let subject_entity = subject_entity.unwrap();
-                let object_entity = object_entity.unwrap();
-                
-                let local_time: DateTime<Local> = Local::now();
-
-                match RelationshipType::from_str(predicate) {
-                    Ok(rel_type) => {
-                        let relationship_fact = Fact::RelationshipAdded {
-                            source_id: subject_entity.id,
-                            target_id: object_entity.id,
-                            relationship_type: rel_type.to_string(),
-                            timestamp: local_time,
-                            valid_from: 2025, // Or current year / configurable
+            let (Some(subject_id), Some(object_id)) = (subject_id, object_id) else {
+                session.error(out, "Subject or object entity not found.")?;
+                for (name, id) in [(subject, subject_id), (object, object_id)] {
+                    if id.is_none() {
+                        suggest_names(&session.db, name, out)?;
+                    }
+                }
+                return Ok(true);
+            };
+
+            let timestamp = Utc::now();
+
+            match RelationshipType::from_str(predicate) {
+                Ok(rel_type) => {
+                    let mut facts = vec![Fact::RelationshipAdded {
+                        source_id: subject_id,
+                        target_id: object_id,
+                        relationship_type: rel_type.to_string(),
+                        timestamp,
+                        valid_from: 2025, // Or current year / configurable
+                        valid_to: None,
+                    }];
+
+                    // Opt-in: also record the reverse edge for types with a configured reciprocal
+                    if let Some(reverse_type) = session.reciprocals.get(&rel_type.to_string()) {
+                        facts.push(Fact::RelationshipAdded {
+                            source_id: object_id,
+                            target_id: subject_id,
+                            relationship_type: reverse_type.clone(),
+                            timestamp,
+                            valid_from: 2025,
                             valid_to: None,
-                        };
-                        let fact_store = FactStore {
-                            facts: vec![relationship_fact]
-                        };
-                        
-                        db.add_fact(fact_store);
-                        println!("{}Relationship '{}' -> '{}' added.{}", GREEN, subject, object, RESET);
+                        });
                     }
-                    Err(_) => {
-                        println!("{}Invalid relationship type: {}{}", RED, predicate, RESET);
+
+                    let fact_store = FactStore { facts };
+
+                    let errors = session.db.add_fact(fact_store);
+                    if errors.is_empty() {
+                        writeln!(out, "{}Relationship '{}' -> '{}' added.{}", GREEN, subject, object, RESET)?;
                     }
+                    for e in errors {
+                        session.error(out, &format!("Relationship not added: {}", e))?;
+                    }
+                }
+                Err(e) => {
+                    session.error(out, &e.to_string())?;
+                }
+            }
+        }
+        "query" => {
+            match query_results(&session.db, &args) {
+                Ok(results) => {
+                    let summaries: Vec<EntitySummary> = results.iter().map(|e| EntitySummary::of(e)).collect();
+                    session.output.emit(out, &summaries, |_| format_entity_list(&results, session.label_key.as_deref()))?
+                }
+                Err(e) => session.error(out, &e)?,
+            }
+        }
+        "search" | "search-type" => {
+            match simple_search_query(&cmd.to_lowercase(), &args) {
+                Ok(query) => {
+                    let results = search_entities(&session.db, query);
+                    let summaries: Vec<EntitySummary> = results.iter().map(|e| EntitySummary::of(e)).collect();
+                    session.output.emit(out, &summaries, |_| format_entity_list(&results, session.label_key.as_deref()))?
                 }
+                Err(e) => session.error(out, &e)?,
             }
-            "query" => {
-                println!("{}Query feature is not implemented yet.{}", RED, RESET);
+        }
+        "show-entity" => {
+            if args.is_empty() {
+                session.error(out, "Usage: show-entity <name>")?;
+                return Ok(true);
             }
-            "build-case" => {
-                if args.len() < 1 {
-                    println!("{}Usage: build-case <case_name>{}", GREEN, RESET);
-                    continue;
+            match find_entity_by_name(&session.db, args[0]) {
+                Some(entity) => session.output.emit(out, entity, |e| {
+                    let mut lines = vec![
+                        format!("{}{}{} ({})", CYAN, e.display_name(session.label_key.as_deref()), RESET, e.entity_type.to_string()),
+                        format!("  id: {}", e.id),
+                    ];
+                    for (key, value) in &e.properties {
+                        lines.push(format!("  {}: {}", key, value));
+                    }
+                    lines.join("\n")
+                })?,
+                None => session.error(out, &format!("Entity '{}' not found.", args[0]))?,
+            }
+        }
+        "facts" => {
+            // No name and no focus lists the whole log
+            let facts: Vec<&Fact> = match (args.first(), entity_or_focus(session, args.first().copied())) {
+                (_, Some(entity)) => {
+                    let ids = [entity.id];
+                    session.db.facts().iter().filter(|f| f.involves_any(&ids)).collect()
+                }
+                (Some(name), None) => {
+                    session.error(out, &format!("Entity '{}' not found.", name))?;
+                    return Ok(true);
+                }
+                (None, None) => session.db.facts().iter().collect(),
+            };
+            let db = &session.db;
+            let label_key = session.label_key.as_deref();
+            let relative = session.relative_times;
+            session.output.emit(out, &facts, |facts| {
+                if facts.is_empty() {
+                    return format!("{}No facts recorded.{}", YELLOW, RESET);
+                }
+                facts.iter().map(|f| format_fact(f, db, relative, label_key)).collect::<Vec<_>>().join("\n")
+            })?;
+        }
+        "focus" => {
+            if args.is_empty() {
+                match session.focus.and_then(|id| session.db.get_entity(&id)) {
+                    Some(entity) => writeln!(out, "{}Focus: {} [{}]{}", CYAN, entity.display_name(session.label_key.as_deref()), entity.id, RESET)?,
+                    None => writeln!(out, "{}No focus set.{}", YELLOW, RESET)?,
+                }
+                return Ok(true);
+            }
+            let target = args.join(" ");
+            match find_entity_by_name(&session.db, &target) {
+                Some(entity) => {
+                    writeln!(out, "{}Focused on {} [{}].{}", GREEN, entity.display_name(session.label_key.as_deref()), entity.id, RESET)?;
+                    session.focus = Some(entity.id);
+                }
+                None => session.error(out, &format!("Entity '{}' not found.", target))?,
+            }
+        }
+        "unfocus" => {
+            session.focus = None;
+            writeln!(out, "{}Focus cleared.{}", GREEN, RESET)?;
+        }
+        "update-entity" => {
+            let [name, key, value] = args.as_slice() else {
+                session.error(out, "Usage: update-entity <name> <key> <value> (quote multi-word values)")?;
+                return Ok(true);
+            };
+            let Some(entity_id) = find_entity_by_name(&session.db, name).map(|e| e.id) else {
+                session.error(out, &format!("Entity '{}' not found.", name))?;
+                return Ok(true);
+            };
+
+            let mut updated_properties = BTreeMap::new();
+            updated_properties.insert(key.to_string(), value.to_string());
+            let update = FactStore {
+                facts: vec![Fact::EntityUpdated { entity_id, timestamp: Utc::now(), updated_properties }],
+            };
+            match session.db.add_facts_atomic(update) {
+                Ok(()) => writeln!(out, "{}Set {} = {} on '{}'.{}", GREEN, key, value, name, RESET)?,
+                Err(e) => session.error(out, &format!("Entity not updated: {}", e))?,
+            }
+        }
+        "remove-fact" => {
+            let [subject, predicate, object] = args.as_slice() else {
+                session.error(out, "Usage: remove-fact <subject> <predicate> <object>")?;
+                return Ok(true);
+            };
+            if report_ambiguous(session, subject, out)? || report_ambiguous(session, object, out)? {
+                return Ok(true);
+            }
+            let (Some(source_id), Some(target_id)) = (
+                find_entity_by_name(&session.db, subject).map(|e| e.id),
+                find_entity_by_name(&session.db, object).map(|e| e.id),
+            ) else {
+                session.error(out, "Subject or object entity not found.")?;
+                return Ok(true);
+            };
+            if !session.db.merged_relationship(&source_id, &target_id).iter().any(|(rel_type, _)| rel_type == predicate) {
+                writeln!(out, "{}No {} relationship from '{}' to '{}'.{}", YELLOW, predicate, subject, object, RESET)?;
+                return Ok(true);
+            }
+
+            // Scoped to the predicate, so other relationships between the pair survive
+            let invalidation = FactStore {
+                facts: vec![Fact::RelationshipInvalidated {
+                    source_id,
+                    target_id,
+                    timestamp: Utc::now(),
+                    relationship_type: Some(predicate.to_string()),
+                }],
+            };
+            match session.db.add_facts_atomic(invalidation) {
+                Ok(()) => writeln!(out, "{}Relationship {} --{}--> {} invalidated.{}", GREEN, subject, predicate, object, RESET)?,
+                Err(e) => session.error(out, &format!("Relationship not invalidated: {}", e))?,
+            }
+        }
+        "undo" => {
+            // Describe the facts before they go, while their entities still resolve
+            let descriptions: Vec<String> = session.db.last_batch().iter()
+                .map(|f| format_fact(f, &session.db, session.relative_times, session.label_key.as_deref()))
+                .collect();
+            if descriptions.is_empty() {
+                writeln!(out, "{}Nothing to undo.{}", YELLOW, RESET)?;
+                return Ok(true);
+            }
+            session.db.undo_last();
+            for description in descriptions {
+                writeln!(out, "{}Undone: {}{}", GREEN, description, RESET)?;
+            }
+        }
+        "add-alias" => {
+            let [name, alias] = args.as_slice() else {
+                session.error(out, "Usage: add-alias <name> <alias> (quote names with spaces)")?;
+                return Ok(true);
+            };
+            if alias.contains(',') || alias.trim().is_empty() {
+                session.error(out, "An alias can't be blank or contain commas.")?;
+                return Ok(true);
+            }
+            let Some(entity) = find_entity_by_name(&session.db, name) else {
+                session.error(out, &format!("Entity '{}' not found.", name))?;
+                return Ok(true);
+            };
+            if entity.is_called(alias) {
+                writeln!(out, "{}'{}' already answers to '{}'.{}", YELLOW, name, alias, RESET)?;
+                return Ok(true);
+            }
+
+            // Aliases live in a property so the full list is in the log with each change
+            let mut aliases = entity.aliases.clone();
+            aliases.push(alias.to_string());
+            let mut updated_properties = BTreeMap::new();
+            updated_properties.insert(ALIASES_KEY.to_string(), aliases.join(","));
+            let entity_id = entity.id;
+            let update = FactStore {
+                facts: vec![Fact::EntityUpdated { entity_id, timestamp: Utc::now(), updated_properties }],
+            };
+            match session.db.add_facts_atomic(update) {
+                Ok(()) => writeln!(out, "{}'{}' is now also known as '{}'.{}", GREEN, name, alias, RESET)?,
+                Err(e) => session.error(out, &format!("Alias not added: {}", e))?,
+            }
+        }
+        "delete-entity" => {
+            if args.is_empty() {
+                session.error(out, "Usage: delete-entity <name>")?;
+                return Ok(true);
+            }
+            let name = args.join(" ");
+            let Some(entity_id) = find_entity_by_name(&session.db, &name).map(|e| e.id) else {
+                session.error(out, &format!("Entity '{}' not found.", name))?;
+                return Ok(true);
+            };
+            // Goes through the log like delete-query, so its relationships are invalidated too
+            if let Err(e) = session.db.delete_entities(&[entity_id]) {
+                session.error(out, &format!("Entity not deleted: {}", e))?;
+                return Ok(true);
+            }
+            if session.focus == Some(entity_id) {
+                session.focus = None;
+            }
+            writeln!(out, "{}Deleted entity '{}'.{}", GREEN, name, RESET)?;
+        }
+        "delete-query" => {
+            // `--yes` skips the confirmation prompt, which a script can't answer
+            let (flags, args): (Vec<&str>, Vec<&str>) = args.iter().partition(|arg| **arg == "--yes");
+            if args.is_empty() {
+                session.error(out, "Usage: delete-query <query...> [--yes] (e.g. delete-query type:Unknown)")?;
+                return Ok(true);
+            }
+            let query = match parse_query(&args.join(" ")) {
+                Ok(query) => query,
+                Err(e) => {
+                    session.error(out, &e.to_string())?;
+                    return Ok(true);
+                }
+            };
+            let matches = search_entities(&session.db, query);
+            if matches.is_empty() {
+                writeln!(out, "{}No matching entities.{}", YELLOW, RESET)?;
+                return Ok(true);
+            }
+            let ids: Vec<Uuid> = matches.iter().map(|e| e.id).collect();
+            if !flags.is_empty() {
+                match session.db.delete_entities(&ids) {
+                    Ok(deleted) => writeln!(out, "{}Deleted {} entities.{}", GREEN, deleted, RESET)?,
+                    Err(e) => session.error(out, &format!("Nothing deleted: {}", e))?,
+                }
+                return Ok(true);
+            }
+            if session.scripted {
+                session.error(out, "delete-query can't ask for confirmation in a script; add --yes to delete.")?;
+                return Ok(true);
+            }
+            writeln!(out, "{}", format_entity_list(&matches, session.label_key.as_deref()))?;
+            writeln!(out, "{}Delete these {} entities and their relationships? [y/N]{}", YELLOW, matches.len(), RESET)?;
+            session.pending_delete = Some(ids);
+        }
+        "timeline" => {
+            // Dates are recognised by shape; any other words form the entity name
+            let (dates, words): (Vec<&str>, Vec<&str>) = args.iter().partition(|arg| parse_date(arg).is_some());
+            if dates.len() > 2 {
+                session.error(out, "Usage: timeline [entity_name] [from YYYY-MM-DD] [to YYYY-MM-DD]")?;
+                return Ok(true);
+            }
+            let mut query = TimelineQuery {
+                from: dates.first().and_then(|d| parse_date(d)),
+                // `to` covers the whole of its day
+                to: dates.get(1).and_then(|d| parse_date(d)).map(|d| d + chrono::Duration::days(1) - chrono::Duration::nanoseconds(1)),
+                ..Default::default()
+            };
+            if !words.is_empty() {
+                let name = words.join(" ");
+                match find_entity_by_name(&session.db, &name) {
+                    Some(entity) => query.entity_id = Some(entity.id),
+                    None => {
+                        session.error(out, &format!("Entity '{}' not found.", name))?;
+                        return Ok(true);
+                    }
+                }
+            }
+
+            let timeline = generate_timeline(&session.db, &query);
+            let db = &session.db;
+            let label_key = session.label_key.as_deref();
+            let relative = session.relative_times;
+            session.output.emit(out, &timeline.facts, |facts| {
+                if facts.is_empty() {
+                    return format!("{}No facts in that range.{}", YELLOW, RESET);
+                }
+                facts.iter().map(|f| format_fact(f, db, relative, label_key)).collect::<Vec<_>>().join("\n")
+            })?;
+        }
+        "balance" => {
+            if args.is_empty() {
+                session.error(out, "Usage: balance <name>")?;
+                return Ok(true);
+            }
+            match find_entity_by_name(&session.db, args[0]) {
+                Some(entity) => {
+                    let (incoming, outgoing) = session.db.io_balance(&entity.id);
+                    let role = match (incoming, outgoing) {
+                        (0, 0) => "isolated",
+                        (0, _) => "pure source",
+                        (_, 0) => "pure sink",
+                        _ => "mixed",
+                    };
+                    let balance = serde_json::json!({ "incoming": incoming, "outgoing": outgoing, "role": role });
+                    session.output.emit(out, &balance, |_| {
+                        format!("{}: {} in / {} out ({})", entity.display_name(session.label_key.as_deref()), incoming, outgoing, role)
+                    })?;
+                }
+                None => session.error(out, &format!("Entity '{}' not found.", args[0]))?,
+            }
+        }
+        "path" => {
+            let [from_name, to_name] = args.as_slice() else {
+                session.error(out, "Usage: path <name_a> <name_b>")?;
+                return Ok(true);
+            };
+            let (Some(from), Some(to)) = (find_entity_by_name(&session.db, from_name), find_entity_by_name(&session.db, to_name)) else {
+                session.error(out, "One or both entities not found.")?;
+                return Ok(true);
+            };
+            match session.db.shortest_path(from.id, to.id) {
+                Some(path) => {
+                    let db = &session.db;
+                    let label_key = session.label_key.as_deref();
+                    session.output.emit(out, &path, |path| {
+                        let mut chain = db.get_entity(&path[0]).map(|e| e.display_name(label_key)).unwrap_or_default();
+                        for hop in path.windows(2) {
+                            // Parallel edges are summarised by the first type, alphabetically
+                            let rel = db.merged_relationship(&hop[0], &hop[1]).into_iter().next().map(|(t, _)| t).unwrap_or_default();
+                            let next = db.get_entity(&hop[1]).map(|e| e.display_name(label_key)).unwrap_or_default();
+                            chain.push_str(&format!(" --{}--> {}", rel, next));
+                        }
+                        chain
+                    })?;
+                }
+                None => writeln!(out, "{}No path from '{}' to '{}'.{}", YELLOW, from_name, to_name, RESET)?,
+            }
+        }
+        "snapshot" => {
+            let Some(year) = args.first().and_then(|y| y.parse::<i64>().ok()) else {
+                session.error(out, "Usage: snapshot <year>")?;
+                return Ok(true);
+            };
+            let active = session.db.relationships_valid_at(year);
+            let db = &session.db;
+            let label_key = session.label_key.as_deref();
+            session.output.emit(out, active.as_slice(), |rels| {
+                if rels.is_empty() {
+                    return format!("{}No relationships active in {}.{}", YELLOW, year, RESET);
+                }
+                let label = |id: &Uuid| db.get_entity(id).map(|e| e.display_name(label_key)).unwrap_or_else(|| "Unknown".to_string());
+                rels.iter()
+                    .map(|rel| {
+                        let until = rel.valid_to.map(|to| to.to_string()).unwrap_or_default();
+                        format!("  {} --{}--> {} ({}-{})", label(&rel.source_id), rel.relationship_type.to_string(), label(&rel.target_id), rel.valid_from, until)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })?;
+        }
+        "clusters" => {
+            let components = session.db.connected_components();
+            let db = &session.db;
+            let label_key = session.label_key.as_deref();
+            session.output.emit(out, &components, |components| {
+                if components.is_empty() {
+                    return format!("{}The graph is empty.{}", YELLOW, RESET);
+                }
+                components.iter()
+                    .enumerate()
+                    .map(|(i, ids)| {
+                        let names: Vec<String> = ids.iter()
+                            .filter_map(|id| db.get_entity(id))
+                            .map(|e| e.display_name(label_key))
+                            .collect();
+                        format!("  Cluster {} ({} entities): {}", i + 1, ids.len(), names.join(", "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })?;
+        }
+        "colleagues" => {
+            if args.is_empty() {
+                session.error(out, "Usage: colleagues <name>")?;
+                return Ok(true);
+            }
+            match find_entity_by_name(&session.db, args[0]) {
+                Some(entity) => {
+                    let colleagues = session.db.co_related(&entity.id, &RelationshipType::WorksAt.to_string());
+                    session.output.emit(out, colleagues.as_slice(), |e| format_entity_list(e, session.label_key.as_deref()))?;
+                }
+                None => session.error(out, &format!("Entity '{}' not found.", args[0]))?,
+            }
+        }
+        "expand" => {
+            // The relationship types come last as `via:A,B`; everything before is the name (or the focus, if omitted)
+            let via = match args.last().and_then(|arg| arg.strip_prefix("via:")) {
+                Some(via) if args.len() >= 2 || session.focus.is_some() => via,
+                _ => {
+                    session.error(out, "Usage: expand [name] via:<type>[,<type>...]")?;
+                    return Ok(true);
                 }
-                
-                let seed_name = args[0];
-                let depth = if args.len() > 1 {
-                    args[1].parse::<usize>().unwrap_or(2)
-                } else {
-                    2
+            };
+            let name = args[..args.len() - 1].join(" ");
+            let rel_types: Vec<String> = via.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect();
+            match entity_or_focus(session, (!name.is_empty()).then_some(name.as_str())) {
+                Some(entity) => {
+                    let neighbours = session.db.neighbours_via_any(&entity.id, &rel_types);
+                    session.output.emit(out, neighbours.as_slice(), |e| format_entity_list(e, session.label_key.as_deref()))?;
+                }
+                None => session.error(out, &format!("Entity '{}' not found.", name))?,
+            }
+        }
+        "dupes" => {
+            let duplicates = session.db.duplicate_edges();
+            let label_key = session.label_key.as_deref();
+            session.output.emit(out, &duplicates, |duplicates| {
+                if duplicates.is_empty() {
+                    return format!("{}No duplicate edges.{}", GREEN, RESET);
+                }
+                let label = |id: &Uuid| session.db.get_entity(id).map(|e| e.display_name(label_key)).unwrap_or_else(|| id.to_string());
+                duplicates.iter()
+                    .map(|(source, target, rel_type, count)| format!("  {} --{}--> {} (x{})", label(source), rel_type, label(target), count))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })?;
+        }
+        "similar" => {
+            if args.is_empty() {
+                session.error(out, "Usage: similar <name>")?;
+                return Ok(true);
+            }
+            match find_entity_by_name(&session.db, args[0]) {
+                Some(entity) => {
+                    let similar = session.db.similar_entities(&entity.id, 10);
+                    let label_key = session.label_key.as_deref();
+                    session.output.emit(out, &similar, |similar| {
+                        if similar.is_empty() {
+                            return format!("{}No similar entities.{}", YELLOW, RESET);
+                        }
+                        similar.iter()
+                            .enumerate()
+                            .map(|(i, (id, score))| {
+                                let label = session.db.get_entity(id).map(|e| e.display_name(label_key)).unwrap_or_else(|| id.to_string());
+                                format!("  {}. {} ({:.2}) [{}]", i + 1, label, score, id)
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    })?;
+                }
+                None => session.error(out, &format!("Entity '{}' not found.", args[0]))?,
+            }
+        }
+        "merged-view" => {
+            if args.len() < 2 {
+                session.error(out, "Usage: merged-view <name_a> <name_b>")?;
+                return Ok(true);
+            }
+            match (find_entity_by_name(&session.db, args[0]), find_entity_by_name(&session.db, args[1])) {
+                (Some(a), Some(b)) => {
+                    let view = serde_json::json!({
+                        "forward": session.db.merged_relationship(&a.id, &b.id),
+                        "backward": session.db.merged_relationship(&b.id, &a.id),
+                    });
+                    let label_key = session.label_key.as_deref();
+                    session.output.emit(out, &view, |_| {
+                        let mut lines = Vec::new();
+                        for (source, target) in [(a, b), (b, a)] {
+                            for (rel_type, count) in session.db.merged_relationship(&source.id, &target.id) {
+                                lines.push(format!(
+                                    "  {} --{} (x{})--> {}",
+                                    source.display_name(label_key), rel_type, count, target.display_name(label_key)
+                                ));
+                            }
+                        }
+                        if lines.is_empty() {
+                            format!("{}No relationships between '{}' and '{}'.{}", YELLOW, a.display_name(label_key), b.display_name(label_key), RESET)
+                        } else {
+                            lines.join("\n")
+                        }
+                    })?;
+                }
+                _ => session.error(out, "One or both entities not found.")?,
+            }
+        }
+        "dossier" => {
+            if args.is_empty() {
+                session.error(out, "Usage: dossier <name>")?;
+                return Ok(true);
+            }
+            let entity_id = match find_entity_by_name(&session.db, args[0]) {
+                Some(entity) => entity.id,
+                None => {
+                    session.error(out, &format!("Entity '{}' not found.", args[0]))?;
+                    return Ok(true);
+                }
+            };
+            let facts = session.db.log_by_entity().remove(&entity_id).unwrap_or_default();
+            let db = &session.db;
+            let label_key = session.label_key.as_deref();
+            let relative = session.relative_times;
+            session.output.emit(out, &facts, |facts| {
+                let mut lines = vec![format!("{}=== Dossier: {} ({} facts) ==={}", CYAN, args[0], facts.len(), RESET)];
+                lines.extend(facts.iter().map(|f| format_fact(f, db, relative, label_key)));
+                lines.join("\n")
+            })?;
+        }
+        "stats" => {
+            let stats = GraphStats {
+                entities: session.db.graph.node_count(),
+                relationships: session.db.graph.edge_count(),
+                facts: session.db.fact_count(),
+                lifecycle: session.db.lifecycle_summary(),
+                most_connected: session.db.most_connected(5),
+            };
+            let db = &session.db;
+            let label_key = session.label_key.as_deref();
+            session.output.emit(out, &stats, |s| {
+                let mut text = format!(
+                    "Entities: {}\nRelationships: {}\nFacts: {}\n  created: {}  updated: {}  deleted: {}  merged: {}\n  relationships added: {}  invalidated: {}",
+                    s.entities, s.relationships, s.facts,
+                    s.lifecycle.entities_created, s.lifecycle.entities_updated, s.lifecycle.entities_deleted, s.lifecycle.entities_merged,
+                    s.lifecycle.relationships_added, s.lifecycle.relationships_invalidated
+                );
+                if !s.most_connected.is_empty() {
+                    text.push_str("\nMost connected:");
+                    for (i, (id, degree)) in s.most_connected.iter().enumerate() {
+                        let label = db.get_entity(id).map(|e| e.display_name(label_key)).unwrap_or_else(|| id.to_string());
+                        text.push_str(&format!("\n  {}. {} ({} relationships)", i + 1, label, degree));
+                    }
+                }
+                text
+            })?;
+        }
+        "build-case" => {
+            if args.is_empty() && session.focus.is_none() {
+                session.error(out, "Usage: build-case [case_name] [max_depth] (or set a focus)")?;
+                return Ok(true);
+            }
+
+            let depth = if args.len() > 1 {
+                args[1].parse::<usize>().unwrap_or(2)
+            } else {
+                2
+            };
+
+            let ambiguous = match args.first() {
+                Some(name) => report_ambiguous(session, name, out)?,
+                None => false,
+            };
+            if ambiguous {
+                return Ok(true);
+            }
+
+            if let Some(seed_entity) = entity_or_focus(session, args.first().copied()) {
+                let seed_name = seed_entity.name.clone();
+                let builder = CaseBuilder::new(&session.db, seed_entity.id)
+                    .with_max_depth(depth);
+
+                let case = builder.build(
+                    &format!("Case around '{}'", seed_name),
+                    "Auto-generated case from CLI",
+                );
+
+                display_case(out, &case, &session.db, session.relative_times, session.label_key.as_deref())?;
+                session.last_case = Some(case);
+
+            } else {
+                session.error(out, &format!("Entity '{}' not found.", args.first().unwrap_or(&"<focus>")))?;
+            }
+        }
+        "report" => {
+            // Same seed and depth handling as build-case, with the output file first
+            let Some((file, rest)) = args.split_first() else {
+                session.error(out, "Usage: report <case_file.md> [entity_name] [max_depth]")?;
+                return Ok(true);
+            };
+            let depth = rest.get(1).and_then(|d| d.parse::<usize>().ok()).unwrap_or(2);
+            let Some(seed_entity) = entity_or_focus(session, rest.first().copied()) else {
+                session.error(out, &format!("Entity '{}' not found.", rest.first().unwrap_or(&"<focus>")))?;
+                return Ok(true);
+            };
+            let case = CaseBuilder::new(&session.db, seed_entity.id)
+                .with_max_depth(depth)
+                .build(&format!("Case around '{}'", seed_entity.name), "Auto-generated case from CLI");
+            match std::fs::write(file, case.to_markdown(&session.db, session.label_key.as_deref())) {
+                Ok(_) => writeln!(out, "{}Case report written to {}{}", GREEN, file, RESET)?,
+                Err(e) => session.error(out, &format!("Failed to write report: {}", e))?,
+            }
+            session.last_case = Some(case);
+        }
+        "compare" => {
+            if args.len() < 2 {
+                session.error(out, "Usage: compare <name_a> <name_b>")?;
+                return Ok(true);
+            }
+            match (find_entity_by_name(&session.db, args[0]), find_entity_by_name(&session.db, args[1])) {
+                (Some(a), Some(b)) => {
+                    let comparison = compare_entities(a, b);
+                    let label_key = session.label_key.as_deref();
+                    session.output.emit(out, &comparison, |c| c.to_table(&a.display_name(label_key), &b.display_name(label_key)))?;
+                }
+                _ => session.error(out, "One or both entities not found.")?,
+            }
+        }
+        "fix-prop" => {
+            if args.len() < 3 {
+                session.error(out, "Usage: fix-prop <key> <from> <to>")?;
+                return Ok(true);
+            }
+            match session.db.replace_property_value(args[0], args[1], args[2]) {
+                Ok(0) => writeln!(out, "{}No entities have {} = '{}'.{}", YELLOW, args[0], args[1], RESET)?,
+                Ok(changed) => writeln!(out, "{}Updated '{}' on {} entities: '{}' -> '{}'.{}", GREEN, args[0], changed, args[1], args[2], RESET)?,
+                Err(e) => session.error(out, &format!("Nothing updated: {}", e))?,
+            }
+        }
+        "watch" => {
+            if args.is_empty() {
+                session.error(out, "Usage: watch <file.jsonl>")?;
+                return Ok(true);
+            }
+            let mut file = match std::fs::File::open(args[0]) {
+                Ok(file) => file,
+                Err(e) => {
+                    session.error(out, &format!("Cannot watch {}: {}", args[0], e))?;
+                    return Ok(true);
+                }
+            };
+
+            // Only report facts appended from now on
+            let mut tail = match file.metadata() {
+                Ok(metadata) => FactTail::starting_at(metadata.len()),
+                Err(e) => {
+                    session.error(out, &format!("Cannot watch {}: {}", args[0], e))?;
+                    return Ok(true);
+                }
+            };
+
+            // Stop on Ctrl+C; the REPL keeps stdin to itself, so it can't be read here
+            if !catch_watch_interrupts() {
+                session.error(out, "Cannot watch: Ctrl+C can't be caught to stop watching")?;
+                return Ok(true);
+            }
+            WATCH_INTERRUPTED.store(false, Ordering::SeqCst);
+            WATCHING.store(true, Ordering::SeqCst);
+
+            writeln!(out, "{}Watching {} for new facts. Press Ctrl+C to stop.{}", CYAN, args[0], RESET)?;
+            while !WATCH_INTERRUPTED.load(Ordering::SeqCst) {
+                let facts = match tail.poll(&mut file) {
+                    Ok(facts) => facts,
+                    Err(e) => {
+                        session.error(out, &format!("Stopped reading {}: {}", args[0], e))?;
+                        break;
+                    }
                 };
-                
-                if let Some(seed_entity) = find_entity_by_name(&db, seed_name) {
-                    let builder = CaseBuilder::new(&db, seed_entity.id)
-                        .with_max_depth(depth);
-                    
-                    let case = builder.build(
-                        &format!("Case around '{}'", seed_name),
-                        "Auto-generated case from CLI",
-                    );
-                    
-                    display_case(&case, &db);
-                    
-                } else {
-                    println!("{}Entity '{}' not found.{}", RED, seed_name, RESET);
-                }
-            }
-            "save" => {
-                match db.persist_facts(data_file) {
-                    Ok(_) => println!("{}Graph saved to {}{}", GREEN, data_file, RESET),
-                    Err(e) => println!("{}Failed to save graph: {}{}", RED, e, RESET),
-                }
-            }
-            "load" => {
-                match GraphDb::load_from_file(data_file) {
-                    Ok(loaded_db) => {
-                        db = loaded_db;
-                        println!("{}Graph loaded from {}{}", GREEN, data_file, RESET);
-                    }
-                    Err(e) => println!("{}Failed to load graph: {}{}", RED, e, RESET),
-                }
-            }
-            "help" => {
-                println!("{}Available commands:{}", GREEN, RESET);
-                println!("{}-------------------------------------------------------------------------------------------{}", GREEN, RESET);
-                println!("  {}add-entity{}      <name> <entity_type>                - Add a new entity", GREEN, RESET);
-                println!("  {}add-fact{}        <subject> <predicate> <object>      - Add a new fact", GREEN, RESET);
-                //println!("  query <query>");
-                println!("  {}build-case{}      <case_name> [max_depth]             - Generate a case from an entity", GREEN, RESET);
-                println!("  {}save{}                                                - Save the current graph to a file", YELLOW, RESET);
-                println!("  {}load{}                                                - Load graph from a file", CYAN, RESET);
-                println!("  {}exit{}                                                - Exit the CLI", RED, RESET);
-                println!("{}--------------------------------------------------------------------------------------------{}", GREEN, RESET);
-            }
-            "exit" | "quit" => {
-                println!("{}Exiting...{}", RED, RESET);
-                println!(
-                    "{}{}{}",
-                    RED,
-                    r#"
+                for fact in facts {
+                    match fact {
+                        Ok(fact) => session.output.emit(out, &fact, |f| format_fact(f, &session.db, session.relative_times, session.label_key.as_deref()))?,
+                        Err(e) => session.output.warning(out, &format!("Skipping unreadable fact line: {}", e))?,
+                    }
+                }
+                out.flush()?;
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+            WATCHING.store(false, Ordering::SeqCst);
+            writeln!(out, "{}Stopped watching {}.{}", CYAN, args[0], RESET)?;
+        }
+        "why-fact" => {
+            let Some(case) = &session.last_case else {
+                writeln!(out, "{}No case built yet. Run 'build-case <name>' first.{}", YELLOW, RESET)?;
+                return Ok(true);
+            };
+            match args.first().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n >= 1 && n <= case.facts.len() => {
+                    let fact = &case.facts[n - 1];
+                    writeln!(out, "{}", format_fact(fact, &session.db, session.relative_times, session.label_key.as_deref()))?;
+                    writeln!(out, "  {}", case.explain_fact_inclusion(fact))?;
+                }
+                _ => {
+                    // Without a valid number, list the facts so the user can pick one
+                    let listing: Vec<String> = case.facts.iter()
+                        .enumerate()
+                        .map(|(i, fact)| format!("  {:>3}. {}", i + 1, format_fact(fact, &session.db, session.relative_times, session.label_key.as_deref())))
+                        .collect();
+                    session.error(out, "Usage: why-fact <fact_number>")?;
+                    for line in listing {
+                        writeln!(out, "{}", line)?;
+                    }
+                }
+            }
+        }
+        "set" => {
+            match args.as_slice() {
+                ["reciprocal", rel_type, reverse_type] => {
+                    match (RelationshipType::from_str(rel_type), RelationshipType::from_str(reverse_type)) {
+                        (Ok(rel_type), Ok(reverse_type)) => {
+                            writeln!(out, "{}'{}' facts will also add a reverse '{}' edge.{}", GREEN, rel_type.to_string(), reverse_type.to_string(), RESET)?;
+                            session.reciprocals.insert(rel_type.to_string(), reverse_type.to_string());
+                        }
+                        (Err(e), _) | (_, Err(e)) => session.error(out, &e.to_string())?,
+                    }
+                }
+                ["reciprocal", rel_type] => {
+                    if session.reciprocals.remove(*rel_type).is_some() {
+                        writeln!(out, "{}Reciprocal for '{}' removed.{}", GREEN, rel_type, RESET)?;
+                    } else {
+                        writeln!(out, "{}No reciprocal configured for '{}'.{}", YELLOW, rel_type, RESET)?;
+                    }
+                }
+                ["relative-times", "on"] => {
+                    session.relative_times = true;
+                    writeln!(out, "{}Timestamps will be shown relative to now.{}", GREEN, RESET)?;
+                }
+                ["relative-times", "off"] => {
+                    session.relative_times = false;
+                    writeln!(out, "{}Timestamps will be shown as absolute dates.{}", GREEN, RESET)?;
+                }
+                ["color", "on"] => {
+                    session.output = session.output.with_colors(true);
+                    writeln!(out, "{}Colored output is on.{}", GREEN, RESET)?;
+                }
+                ["color", "off"] => {
+                    session.output = session.output.with_colors(false);
+                    writeln!(out, "Colored output is off.")?;
+                }
+                ["unique-names", "on"] => {
+                    session.unique_names = true;
+                    writeln!(out, "{}Entity names must now be unique (case-insensitive).{}", GREEN, RESET)?;
+                }
+                ["unique-names", "off"] => {
+                    session.unique_names = false;
+                    writeln!(out, "{}Duplicate entity names are allowed.{}", GREEN, RESET)?;
+                }
+                ["label-key", key] => {
+                    session.label_key = Some(key.to_string());
+                    writeln!(out, "{}Entities will be labelled by their '{}' property where present.{}", GREEN, key, RESET)?;
+                }
+                ["label-key"] => {
+                    session.label_key = None;
+                    writeln!(out, "{}Entities will be labelled by name.{}", GREEN, RESET)?;
+                }
+                ["rotations", count] => match count.parse::<usize>() {
+                    Ok(count) => {
+                        session.rotations = count;
+                        writeln!(out, "{}'save' will keep the last {} backups.{}", GREEN, count, RESET)?;
+                    }
+                    Err(_) => session.error(out, &format!("Invalid rotation count: {}", count))?,
+                },
+                _ => session.error(out, concat!(
+                    "Usage: set reciprocal <relationship_type> [reverse_type]\n",
+                    "       set relative-times <on|off>\n",
+                    "       set unique-names <on|off>\n",
+                    "       set color <on|off>\n",
+                    "       set rotations <count>\n",
+                    "       set label-key [property]",
+                ))?,
+            }
+        }
+        "export-dot" => {
+            if args.is_empty() {
+                session.error(out, "Usage: export-dot <file.dot>")?;
+                return Ok(true);
+            }
+            match std::fs::write(args[0], session.db.to_dot(session.label_key.as_deref())) {
+                Ok(_) => writeln!(out, "{}Graph exported to {}{}", GREEN, args[0], RESET)?,
+                Err(e) => session.error(out, &format!("Failed to export graph: {}", e))?,
+            }
+        }
+        "export-graphml" => {
+            if args.is_empty() {
+                session.error(out, "Usage: export-graphml <file.graphml>")?;
+                return Ok(true);
+            }
+            match std::fs::write(args[0], session.db.to_graphml()) {
+                Ok(_) => writeln!(out, "{}Graph exported to {}{}", GREEN, args[0], RESET)?,
+                Err(e) => session.error(out, &format!("Failed to export graph: {}", e))?,
+            }
+        }
+        "save" => {
+            let Some(file) = save_file(session, &args) else {
+                session.error(out, "Usage: save <file> (scripts have no default data file)")?;
+                return Ok(true);
+            };
+            match session.db.persist_facts_with_rotation(&file, session.rotations) {
+                Ok(_) => writeln!(out, "{}Graph saved to {}{}", GREEN, file, RESET)?,
+                Err(e) => session.error(out, &format!("Failed to save graph: {}", e))?,
+            }
+        }
+        "load" => {
+            let Some(file) = save_file(session, &args) else {
+                session.error(out, "Usage: load <file> (scripts have no default data file)")?;
+                return Ok(true);
+            };
+            match GraphDb::load_from_file(&file) {
+                Ok(loaded_db) => {
+                    session.db = loaded_db;
+                    writeln!(out, "{}Graph loaded from {}{}", GREEN, file, RESET)?;
+                    for warning in &session.db.load_warnings {
+                        session.output.warning(out, warning)?;
+                    }
+                }
+                Err(e) => session.error(out, &format!("Failed to load graph: {}", e))?,
+            }
+        }
+        "help" => {
+            writeln!(out, "{}Available commands:{}", GREEN, RESET)?;
+            writeln!(out, "{}-------------------------------------------------------------------------------------------{}", GREEN, RESET)?;
+            writeln!(out, "  {}add-entity{}      <name> <entity_type>                - Add a new entity", GREEN, RESET)?;
+            writeln!(out, "  {}add-fact{}        <subject> <predicate> <object>      - Add a new fact", GREEN, RESET)?;
+            writeln!(out, "  {}remove-fact{}     <subject> <predicate> <object>      - Invalidate a relationship", GREEN, RESET)?;
+            writeln!(out, "  {}query{}           [type:<type>] [name:<text>]         - List entities matching filters (also regex:<re>, key=value, sort:recent|name|type)", GREEN, RESET)?;
+            writeln!(out, "  {}search{}          <substring>                         - List entities whose name contains the text", GREEN, RESET)?;
+            writeln!(out, "  {}search-type{}     <entity_type>                       - List entities of one type", GREEN, RESET)?;
+            writeln!(out, "  {}show-entity{}     <name>                              - Show an entity and its properties", GREEN, RESET)?;
+            writeln!(out, "  {}facts{}           [name]                              - List recorded facts (for one entity or the focus)", GREEN, RESET)?;
+            writeln!(out, "  {}focus{}           [name|uuid]                         - Set (or show) the entity used when a name is left out", GREEN, RESET)?;
+            writeln!(out, "  {}unfocus{}                                             - Clear the current focus", GREEN, RESET)?;
+            writeln!(out, "  {}update-entity{}   <name> <key> <value>                - Set one property (quote values with spaces)", GREEN, RESET)?;
+            writeln!(out, "  {}undo{}                                                - Undo the facts the last command recorded", GREEN, RESET)?;
+            writeln!(out, "  {}add-alias{}       <name> <alias>                      - Record another name the entity is known by", GREEN, RESET)?;
+            writeln!(out, "  {}delete-entity{}   <name>                              - Delete an entity and its relationships", GREEN, RESET)?;
+            writeln!(out, "  {}delete-query{}    <query...> [--yes]                  - Delete every entity matching type:/name:/regex: filters", GREEN, RESET)?;
+            writeln!(out, "  {}timeline{}        [name] [from] [to]                  - List facts in time order (dates as YYYY-MM-DD)", GREEN, RESET)?;
+            writeln!(out, "  {}balance{}         <name>                              - Show incoming vs outgoing relationship counts", GREEN, RESET)?;
+            writeln!(out, "  {}path{}            <name_a> <name_b>                   - Show the shortest chain of relationships from A to B", GREEN, RESET)?;
+            writeln!(out, "  {}snapshot{}        <year>                              - List relationships valid in the given year", GREEN, RESET)?;
+            writeln!(out, "  {}clusters{}                                            - List groups of entities connected to each other", GREEN, RESET)?;
+            writeln!(out, "  {}colleagues{}      <name>                              - List others who work at the same place", GREEN, RESET)?;
+            writeln!(out, "  {}expand{}          [name] via:<type>,<type>            - List neighbours linked by any of the given types", GREEN, RESET)?;
+            writeln!(out, "  {}similar{}         <name>                              - Rank entities sharing neighbours and properties", GREEN, RESET)?;
+            writeln!(out, "  {}merged-view{}     <name_a> <name_b>                   - Count relationships between two entities by type", GREEN, RESET)?;
+            writeln!(out, "  {}dupes{}                                               - List pairs joined by repeated edges of one type", GREEN, RESET)?;
+            writeln!(out, "  {}dossier{}         <name>                              - Show every fact involving one entity", GREEN, RESET)?;
+            writeln!(out, "  {}stats{}                                               - Show counts and the 5 most connected entities", GREEN, RESET)?;
+            writeln!(out, "  {}build-case{}      [case_name] [max_depth]             - Generate a case from an entity", GREEN, RESET)?;
+            writeln!(out, "  {}watch{}           <file.jsonl>                        - Print facts as they are appended to a file", GREEN, RESET)?;
+            writeln!(out, "  {}report{}          <file.md> [name] [max_depth]        - Build a case and write it as a Markdown report", GREEN, RESET)?;
+            writeln!(out, "  {}why-fact{}        <fact_number>                       - Explain why a fact is in the last built case", GREEN, RESET)?;
+            writeln!(out, "  {}compare{}         <name_a> <name_b>                   - Compare two entities' properties side by side", GREEN, RESET)?;
+            writeln!(out, "  {}fix-prop{}        <key> <from> <to>                   - Replace a property value on every entity", GREEN, RESET)?;
+            writeln!(out, "  {}set{}             reciprocal <type> [reverse_type]    - Auto-add (or stop adding) a reverse edge", GREEN, RESET)?;
+            writeln!(out, "  {}set{}             relative-times <on|off>             - Show timestamps as '3 days ago'", GREEN, RESET)?;
+            writeln!(out, "  {}set{}             unique-names <on|off>               - Refuse duplicate entity names", GREEN, RESET)?;
+            writeln!(out, "  {}set{}             color <on|off>                      - Toggle ANSI colors (off with NO_COLOR or --no-color)", GREEN, RESET)?;
+            writeln!(out, "  {}set{}             rotations <count>                   - Keep numbered backups when saving", GREEN, RESET)?;
+            writeln!(out, "  {}set{}             label-key [property]                - Label entities by a property instead of name", GREEN, RESET)?;
+            writeln!(out, "  {}export-dot{}      <file.dot>                          - Export the graph for Graphviz", GREEN, RESET)?;
+            writeln!(out, "  {}export-graphml{}  <file.graphml>                      - Export the graph for Gephi or yEd", GREEN, RESET)?;
+            writeln!(out, "  {}save{}            [file]                              - Save the current graph to a file", YELLOW, RESET)?;
+            writeln!(out, "  {}load{}            [file]                              - Load graph from a file", CYAN, RESET)?;
+            writeln!(out, "  {}exit{}                                                - Exit the CLI", RED, RESET)?;
+            writeln!(out, "{}--------------------------------------------------------------------------------------------{}", GREEN, RESET)?;
+        }
+        "exit" | "quit" => {
+            writeln!(out, "{}Exiting...{}", RED, RESET)?;
+            writeln!(out, 
+                "{}{}{}",
+                RED,
+                r#"
                                     ****************************************************************
                                     * ____    _____   _____   ____    ____     __    __  ____      *
                                     */\  _`\ /\  __`\/\  __`\/\  _`\ /\  _`\  /\ \  /\ \/\  _`\    *
@@ -221,15 +1448,487 @@ pub fn run_h3imd3ll_repl() -> io::Result<()> {
                                     *    \/___/  \/_____/\/_____/\/___/  \/___/     \/_/    \/___/ *
                                     **************************************************************** 
                     "#,
-                    RESET,
-                );
-                break;
-            }
-            _ => {
-                println!("{}Unknown command '{}'. Type 'help' for a list of commands.{}", RED, cmd, RESET);
-            }
+                RESET,
+            )?;
+            return Ok(false);
+        }
+        _ => {
+            session.error(out, &format!("Unknown command '{}'. Type 'help' for a list of commands.", cmd))?;
         }
     }
 
-    Ok(())
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::scratch_path;
+
+    fn session_with(commands: &[&str]) -> Session {
+        let mut session = Session::new(GraphDb::new(), "test_graph_data.json");
+        for command in commands {
+            execute_command(&mut session, command, &mut io::sink()).unwrap();
+        }
+        session
+    }
+
+    #[test]
+    fn test_add_fact_adds_configured_reciprocal_edge() {
+        let session = session_with(&[
+            "set reciprocal WorksAt Employs",
+            "add-entity John Person",
+            "add-entity Acme Company",
+            "add-fact John WorksAt Acme",
+        ]);
+
+        let john = find_entity_by_name(&session.db, "John").unwrap();
+        let acme = find_entity_by_name(&session.db, "Acme").unwrap();
+
+        let outgoing = session.db.get_outgoing_neighbours(&john.id);
+        let reverse = session.db.get_outgoing_neighbours(&acme.id);
+
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].name, "Acme");
+        assert_eq!(reverse.len(), 1);
+        assert_eq!(reverse[0].name, "John");
+
+        // Both relationship facts are in the log alongside the two creations
+        assert_eq!(session.db.event_log.len(), 4);
+    }
+
+    #[test]
+    fn test_add_fact_without_reciprocal_adds_single_edge() {
+        let session = session_with(&[
+            "add-entity John Person",
+            "add-entity Acme Company",
+            "add-fact John WorksAt Acme",
+        ]);
+
+        let acme = find_entity_by_name(&session.db, "Acme").unwrap();
+        assert!(session.db.get_outgoing_neighbours(&acme.id).is_empty());
+    }
+
+    #[test]
+    fn test_query_in_json_mode_emits_entity_summaries() {
+        let mut session = session_with(&[
+            "add-entity John Person",
+            "add-entity Johnny Person",
+            "add-entity Acme Company",
+        ]);
+        session.output = Output::json();
+
+        let mut captured: Vec<u8> = Vec::new();
+        execute_command(&mut session, "query John", &mut captured).unwrap();
+
+        let parsed: Vec<EntitySummary> = serde_json::from_slice(&captured).expect("query output should be a JSON array");
+        let mut names: Vec<String> = parsed.into_iter().map(|e| e.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["John", "Johnny"]);
+    }
+
+    #[test]
+    fn test_search_and_add_entity_in_json_mode_emit_summaries() {
+        let mut session = session_with(&["add-entity John Person", "add-entity Acme Company"]);
+        session.output = Output::json();
+
+        let mut added: Vec<u8> = Vec::new();
+        execute_command(&mut session, "add-entity Johnny Person", &mut added).unwrap();
+        let added: EntitySummary = serde_json::from_slice(&added).expect("add-entity output should be one JSON object");
+        assert_eq!(added.name, "Johnny");
+
+        let mut captured: Vec<u8> = Vec::new();
+        execute_command(&mut session, "search John", &mut captured).unwrap();
+        let mut parsed: Vec<EntitySummary> = serde_json::from_slice(&captured).expect("search output should be a JSON array");
+        parsed.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let john = find_entity_by_name(&session.db, "John").unwrap();
+        assert_eq!(parsed, vec![
+            EntitySummary { id: john.id, name: "John".to_string(), entity_type: "Person".to_string() },
+            added,
+        ]);
+        let raw: Vec<serde_json::Value> = serde_json::from_slice(&captured).unwrap();
+        assert_eq!(raw[0]["type"], "Person");
+    }
+
+    #[test]
+    fn test_script_builds_graph_and_counts_errors() {
+        let script = "# two people and where one works\n\nadd-entity John Person\nadd-entity Acme Company\nadd-fact John WorksAt Acme\n";
+        let mut session = Session::new(GraphDb::new(), "test_graph_data.json");
+        run_script(&mut session, io::Cursor::new(script), &mut io::sink()).unwrap();
+
+        assert_eq!(session.db.entity_count(), 2);
+        assert_eq!(session.db.graph.edge_count(), 1);
+        assert_eq!(session.errors, 0);
+
+        run_script(&mut session, io::Cursor::new("frobnicate\nshow-entity Nobody\n"), &mut io::sink()).unwrap();
+        assert_eq!(session.errors, 2);
+    }
+
+    #[test]
+    fn test_script_counts_usage_errors_and_guards_save_and_delete() {
+        let mut session = Session::for_script();
+        run_script(&mut session, io::Cursor::new("add-entity John\nadd-entity John Person\n"), &mut io::sink()).unwrap();
+        assert_eq!(session.errors, 1);
+
+        // No default data file to overwrite, and no one to answer a prompt
+        run_script(&mut session, io::Cursor::new("save\ndelete-query name:John\nadd-entity Acme Company\n"), &mut io::sink()).unwrap();
+        assert_eq!(session.errors, 3);
+        assert!(session.pending_delete.is_none());
+        assert_eq!(session.db.entity_count(), 2);
+
+        let path = scratch_path("json");
+        let script = format!("delete-query name:John --yes\nsave {}\n", path.display());
+        run_script(&mut session, io::Cursor::new(script), &mut io::sink()).unwrap();
+        let saved = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(session.errors, 3);
+        assert_eq!(saved.entity_count(), 1);
+    }
+
+    #[test]
+    fn test_command_output_can_be_captured() {
+        let mut session = session_with(&[
+            "add-entity John Person",
+            "add-entity Acme Company",
+            "add-fact John WorksAt Acme",
+        ]);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        execute_command(&mut session, "stats", &mut buffer).unwrap();
+        execute_command(&mut session, "show-entity John", &mut buffer).unwrap();
+
+        let captured = String::from_utf8(buffer).unwrap();
+        assert!(captured.contains("Entities: 2"));
+        assert!(captured.contains("Relationships: 1"));
+        assert!(captured.contains("Facts: 3"));
+        assert!(captured.contains("type: Person"));
+    }
+
+    #[test]
+    fn test_custom_entity_type_survives_save_and_load() {
+        let path = scratch_path("json");
+
+        let script = "add-entity x BankAccount\nsave\nexit\n";
+        run_repl(&path, Output::human(), io::Cursor::new(script), &mut io::sink()).unwrap();
+        let db = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let account = db.find_by_name_ci("x").unwrap();
+        assert_eq!(account.entity_type, EntityType::Custom("BankAccount".to_string()));
+        assert_eq!(account.entity_type.to_string(), "BankAccount");
+    }
+
+    #[test]
+    fn test_undo_removes_the_last_added_entity() {
+        let mut session = session_with(&["add-entity John Person", "add-entity Acme Company"]);
+
+        let mut captured: Vec<u8> = Vec::new();
+        execute_command(&mut session, "undo", &mut captured).unwrap();
+
+        assert!(String::from_utf8(captured).unwrap().contains("Undone: "));
+        assert_eq!(session.db.entity_count(), 1);
+        assert!(find_entity_by_name(&session.db, "John").is_some());
+        assert!(find_entity_by_name(&session.db, "Acme").is_none());
+        assert_eq!(session.db.fact_count(), 1);
+    }
+
+    #[test]
+    fn test_undo_takes_back_both_edges_of_a_reciprocal_fact() {
+        let mut session = session_with(&[
+            "set reciprocal Owns OwnedBy",
+            "add-entity John Person",
+            "add-entity Car Vehicle",
+            "add-fact John Owns Car",
+        ]);
+        assert_eq!(session.db.graph.edge_count(), 2);
+
+        execute_command(&mut session, "undo", &mut io::sink()).unwrap();
+
+        assert_eq!(session.db.graph.edge_count(), 0);
+        assert_eq!(session.db.fact_count(), 2);
+        assert_eq!(session.db.entity_count(), 2);
+    }
+
+    #[test]
+    fn test_color_off_leaves_no_escape_sequences() {
+        let mut session = session_with(&["add-entity John Person"]);
+
+        let mut captured: Vec<u8> = Vec::new();
+        execute_command(&mut session, "set color off", &mut captured).unwrap();
+        execute_command(&mut session, "help", &mut captured).unwrap();
+        execute_command(&mut session, "show-entity Nobody", &mut captured).unwrap();
+
+        let captured = String::from_utf8(captured).unwrap();
+        assert!(captured.contains("Available commands:"));
+        assert!(captured.contains("Entity 'Nobody' not found"));
+        assert!(!captured.contains('\x1b'));
+
+        // Turning them back on only affects this session's later output
+        let mut colored: Vec<u8> = Vec::new();
+        execute_command(&mut session, "set color on", &mut io::sink()).unwrap();
+        execute_command(&mut session, "show-entity Nobody", &mut colored).unwrap();
+        assert!(String::from_utf8(colored).unwrap().contains('\x1b'));
+    }
+
+    #[test]
+    fn test_unique_names_setting_controls_duplicates() {
+        let strict = session_with(&[
+            "set unique-names on",
+            "add-entity John Person",
+            "add-entity JOHN Person",
+        ]);
+        assert_eq!(strict.db.graph.node_count(), 1);
+
+        let relaxed = session_with(&[
+            "set unique-names off",
+            "add-entity John Person",
+            "add-entity JOHN Person",
+        ]);
+        assert_eq!(relaxed.db.graph.node_count(), 2);
+        assert_eq!(relaxed.db.entities_named("john").len(), 2);
+    }
+
+    #[test]
+    fn test_label_key_changes_displayed_label() {
+        let mut session = session_with(&["add-entity \"555 0101\" PhoneNumber"]);
+        let phone = find_entity_by_name(&session.db, "555 0101").unwrap().id;
+        let mut updated_properties = BTreeMap::new();
+        updated_properties.insert("owner".to_string(), "John".to_string());
+        session.db.add_fact(FactStore {
+            facts: vec![Fact::EntityUpdated { entity_id: phone, timestamp: Utc::now(), updated_properties }],
+        });
+
+        let mut before: Vec<u8> = Vec::new();
+        execute_command(&mut session, "query 555", &mut before).unwrap();
+        assert!(String::from_utf8(before).unwrap().contains("1. 555 0101 (PhoneNumber)"));
+
+        let mut after: Vec<u8> = Vec::new();
+        execute_command(&mut session, "set label-key owner", &mut io::sink()).unwrap();
+        execute_command(&mut session, "query 555", &mut after).unwrap();
+        assert!(String::from_utf8(after).unwrap().contains("1. John (PhoneNumber)"));
+
+        // The label key is a session setting, so loading another graph keeps it
+        let path = scratch_path("json");
+        session.db.persist_facts(path.to_str().unwrap()).unwrap();
+        execute_command(&mut session, &format!("load {}", path.display()), &mut io::sink()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut reloaded: Vec<u8> = Vec::new();
+        execute_command(&mut session, "query 555", &mut reloaded).unwrap();
+        assert!(String::from_utf8(reloaded).unwrap().contains("1. John (PhoneNumber)"));
+    }
+
+    #[test]
+    fn test_focus_is_set_by_name_or_uuid_and_cleared() {
+        let mut session = session_with(&["add-entity John Person", "add-entity Acme Company", "focus John"]);
+        let john = find_entity_by_name(&session.db, "John").unwrap().id;
+        let acme = find_entity_by_name(&session.db, "Acme").unwrap().id;
+        assert_eq!(session.focus, Some(john));
+
+        execute_command(&mut session, &format!("focus {}", acme), &mut io::sink()).unwrap();
+        assert_eq!(session.focus, Some(acme));
+
+        // An unknown target leaves the focus alone
+        execute_command(&mut session, "focus Nobody", &mut io::sink()).unwrap();
+        assert_eq!(session.focus, Some(acme));
+
+        // Commands without a name act on the focus
+        let mut facts: Vec<u8> = Vec::new();
+        execute_command(&mut session, "facts", &mut facts).unwrap();
+        let facts = String::from_utf8(facts).unwrap();
+        assert!(facts.contains(&acme.to_string()) && !facts.contains(&john.to_string()));
+
+        execute_command(&mut session, "unfocus", &mut io::sink()).unwrap();
+        assert_eq!(session.focus, None);
+    }
+
+    #[test]
+    fn test_delete_query_removes_matches_and_their_edges_after_confirmation() {
+        let mut session = session_with(&[
+            "add-entity Junk1 Unknown",
+            "add-entity Junk2 Unknown",
+            "add-entity John Person",
+            "add-entity Acme Company",
+            "add-fact Junk1 WorksAt Acme",
+            "add-fact John WorksAt Acme",
+            "add-fact Acme LocatedAt Junk2",
+        ]);
+
+        // Anything but yes cancels
+        execute_command(&mut session, "delete-query type:Unknown", &mut io::sink()).unwrap();
+        execute_command(&mut session, "n", &mut io::sink()).unwrap();
+        assert_eq!(session.db.graph.node_count(), 4);
+
+        execute_command(&mut session, "delete-query type:Unknown", &mut io::sink()).unwrap();
+        execute_command(&mut session, "y", &mut io::sink()).unwrap();
+
+        assert!(find_entity_by_name(&session.db, "Junk1").is_none());
+        assert!(find_entity_by_name(&session.db, "Junk2").is_none());
+        assert_eq!(session.db.graph.node_count(), 2);
+        assert_eq!(session.db.graph.edge_count(), 1);
+
+        // The removed edges are recorded as invalidated in the log
+        let invalidated = session.db.event_log.iter().filter(|f| matches!(f, Fact::RelationshipInvalidated { .. })).count();
+        assert_eq!(invalidated, 2);
+    }
+
+    #[test]
+    fn test_delete_prompt_cancels_on_a_command_and_still_runs_it() {
+        let mut session = session_with(&["add-entity Junk Unknown"]);
+
+        execute_command(&mut session, "delete-query type:Unknown", &mut io::sink()).unwrap();
+        execute_command(&mut session, "add-entity Acme Company", &mut io::sink()).unwrap();
+
+        assert!(session.pending_delete.is_none());
+        assert!(find_entity_by_name(&session.db, "Junk").is_some());
+        assert!(find_entity_by_name(&session.db, "Acme").is_some());
+    }
+
+    #[test]
+    fn test_empty_line_in_the_repl_cancels_a_delete_prompt() {
+        let path = scratch_path("json");
+        let script = "add-entity Junk Unknown\ndelete-query type:Unknown\n\ny\nshow-entity Junk\n";
+        let mut captured: Vec<u8> = Vec::new();
+        run_repl(&path, Output::human(), io::Cursor::new(script), &mut captured).unwrap();
+
+        let text = String::from_utf8(captured).unwrap();
+        assert!(text.contains("Deletion cancelled."));
+        assert!(!text.contains("Deleted"));
+    }
+
+    #[test]
+    fn test_repl_saves_to_and_loads_from_given_path() {
+        let path = scratch_path("json");
+
+        let script = "add-entity John Person\nsave\nexit\n";
+        run_repl(&path, Output::human(), io::Cursor::new(script), &mut io::sink()).unwrap();
+        assert!(path.exists());
+
+        // A fresh REPL on the same path picks the saved graph back up
+        let mut captured: Vec<u8> = Vec::new();
+        run_repl(&path, Output::human(), io::Cursor::new("show-entity John\n"), &mut captured).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let captured = String::from_utf8(captured).unwrap();
+        assert!(captured.contains(&format!("Loaded graph from {}", path.display())));
+        assert!(captured.contains("type: Person"));
+    }
+
+    #[test]
+    fn test_history_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("h3imd3ll_history_{}", Uuid::new_v4()));
+        assert!(read_history(&path).unwrap().is_empty());
+
+        let entries: Vec<String> = ["add-entity John Person", "search John"].iter().map(|s| s.to_string()).collect();
+        write_history(&path, &entries).unwrap();
+        assert_eq!(read_history(&path).unwrap(), entries);
+
+        // Only the most recent entries are kept
+        let many: Vec<String> = (0..HISTORY_LIMIT + 5).map(|i| format!("show-entity {}", i)).collect();
+        write_history(&path, &many).unwrap();
+        let restored = read_history(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.len(), HISTORY_LIMIT);
+        assert_eq!(restored[0], "show-entity 5");
+        assert_eq!(restored.last(), many.last());
+    }
+
+    #[test]
+    fn test_search_commands_build_matching_queries() {
+        let query = simple_search_query("search", &["John", "Smith"]).unwrap();
+        assert_eq!(query.name_contains, Some("John Smith".to_string()));
+        assert_eq!(query.entity_type, None);
+
+        let query = simple_search_query("search-type", &["Company"]).unwrap();
+        assert_eq!(query.entity_type, Some(EntityType::Company));
+        assert_eq!(query.name_contains, None);
+
+        assert!(simple_search_query("search", &[]).is_err());
+        assert!(simple_search_query("search-type", &[]).is_err());
+    }
+
+    #[test]
+    fn test_delete_entity_removes_it_from_the_graph() {
+        let mut session = session_with(&["add-entity John Person", "add-entity Acme Company", "add-fact John WorksAt Acme"]);
+        let john = find_entity_by_name(&session.db, "John").unwrap().id;
+
+        execute_command(&mut session, "delete-entity John", &mut io::sink()).unwrap();
+
+        assert!(session.db.get_entity(&john).is_none());
+        assert_eq!(session.db.graph.edge_count(), 0);
+        assert!(matches!(session.db.event_log.last(), Some(Fact::EntityDeleted { entity_id, .. }) if *entity_id == john));
+    }
+
+    #[test]
+    fn test_split_args_keeps_quoted_values_together() {
+        assert_eq!(split_args(r#"update-entity John address "1 High Street""#), vec!["update-entity", "John", "address", "1 High Street"]);
+        assert_eq!(split_args(r#"  note ""  "#), vec!["note", ""]);
+    }
+
+    #[test]
+    fn test_update_entity_sets_quoted_property() {
+        let session = session_with(&["add-entity John Person", r#"update-entity John address "1 High Street""#]);
+
+        let john = find_entity_by_name(&session.db, "John").unwrap();
+        assert_eq!(session.db.get_entity(&john.id).unwrap().properties["address"], "1 High Street");
+    }
+
+    #[test]
+    fn test_add_fact_accepts_uuid_for_ambiguous_name() {
+        let mut session = session_with(&["add-entity John Person", "add-entity John Person", "add-entity Acme Company"]);
+        let second_john = session.db.find_all_by_name("John")[1].id;
+
+        // By name the command refuses to guess
+        execute_command(&mut session, "add-fact John WorksAt Acme", &mut io::sink()).unwrap();
+        assert_eq!(session.db.graph.edge_count(), 0);
+
+        execute_command(&mut session, &format!("add-fact {} WorksAt Acme", second_john), &mut io::sink()).unwrap();
+        let targets = session.db.get_outgoing_neighbours(&second_john);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "Acme");
+    }
+
+    #[test]
+    fn test_remove_fact_invalidates_relationship() {
+        let mut session = session_with(&["add-entity John Person", "add-entity Acme Company", "add-fact John WorksAt Acme"]);
+        let john = find_entity_by_name(&session.db, "John").unwrap().id;
+        assert_eq!(session.db.get_outgoing_neighbours(&john).len(), 1);
+
+        execute_command(&mut session, "remove-fact John WorksAt Acme", &mut io::sink()).unwrap();
+
+        assert!(session.db.get_outgoing_neighbours(&john).is_empty());
+        assert!(matches!(session.db.facts().last(), Some(Fact::RelationshipInvalidated { .. })));
+    }
+
+    #[test]
+    fn test_add_fact_suggests_near_names() {
+        let mut session = session_with(&["add-entity \"John Doe\" Person", "add-entity Acme Company"]);
+
+        let mut captured: Vec<u8> = Vec::new();
+        execute_command(&mut session, "add-fact \"Jon Doe\" WorksAt Acme", &mut captured).unwrap();
+
+        assert!(String::from_utf8(captured).unwrap().contains("Did you mean 'John Doe'?"));
+        assert_eq!(session.db.graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn test_lookup_by_alias_finds_entity() {
+        let session = session_with(&[
+            "add-entity \"Robert Smith\" Person",
+            "add-entity Bob Company",
+            "add-alias \"Robert Smith\" \"Bobby S\"",
+        ]);
+
+        let robert = find_entity_by_name(&session.db, "bobby s").unwrap();
+        assert_eq!(robert.name, "Robert Smith");
+        assert_eq!(robert.aliases, vec!["Bobby S".to_string()]);
+        assert_eq!(find_entity_by_name(&session.db, "Bob").unwrap().entity_type, EntityType::Company);
+
+        let results = search_entities(&session.db, SearchQuery { name_contains: Some("Bobby".to_string()), ..Default::default() });
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, robert.id);
+    }
 }
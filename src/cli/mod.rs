@@ -1,8 +1,30 @@
-use crate::cli::commands::run_h3imd3ll_repl;
+use std::path::Path;
+
+use crate::cli::commands::{run_h3imd3ll_repl, run_h3imd3ll_script};
+use crate::cli::output::Output;
 
 mod commands;
+mod output;
 mod utils;
 
 pub fn run_cli() {
-    run_h3imd3ll_repl().unwrap();
+    // `--script <file>` runs the file's commands and exits with 1 if any of them failed
+    let args: Vec<String> = std::env::args().collect();
+
+    // `--json` switches every entry point to JSON; `--no-color` or a non-empty NO_COLOR turns colors off
+    let output = if args.iter().any(|arg| arg == "--json") {
+        Output::json()
+    } else {
+        Output::human()
+    };
+    let output = output.with_colors(!utils::no_color_requested() && !args.iter().any(|arg| arg == "--no-color"));
+
+    if let Some(path) = args.iter().position(|arg| arg == "--script").and_then(|i| args.get(i + 1)) {
+        let code = run_h3imd3ll_script(Path::new(path), output).unwrap_or_else(|e| {
+            eprintln!("Failed to run script {}: {}", path, e);
+            1
+        });
+        std::process::exit(code);
+    }
+    run_h3imd3ll_repl(output).unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use std::io::{self, Write};
+use serde::Serialize;
+use crate::cli::utils::{RED, RESET, YELLOW};
+
+/// Decides how command results are rendered:
+/// decorated text for analysts, or JSON for scripts driving the CLI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Output {
+    json: bool,
+    colors: bool,
+}
+
+impl Output {
+    /// Human-readable, colored output (the default).
+    pub fn human() -> Self {
+        Output { json: false, colors: true }
+    }
+
+    /// Machine-readable output: every result is printed as a single JSON document.
+    pub fn json() -> Self {
+        Output { json: true, colors: true }
+    }
+
+    /// The same mode with colors turned on or off(`--no-color`, `NO_COLOR`, `set color`).
+    pub fn with_colors(self, colors: bool) -> Self {
+        Output { colors, ..self }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    pub fn colors(&self) -> bool {
+        self.colors
+    }
+
+    /// Renders a command result either as JSON or through the given human formatter.
+    pub fn render<T: Serialize + ?Sized>(&self, result: &T, human: impl FnOnce(&T) -> String) -> String {
+        if self.json {
+            serde_json::to_string(result).unwrap_or_else(|e| format!("{{\"error\":\"{}\"}}", e))
+        } else {
+            human(result)
+        }
+    }
+
+    /// Writes a command result to `out` in the active mode.
+    pub fn emit<T: Serialize + ?Sized>(&self, out: &mut dyn Write, result: &T, human: impl FnOnce(&T) -> String) -> io::Result<()> {
+        writeln!(out, "{}", self.render(result, human))
+    }
+
+    /// Writes an error message to `out`, as `{"error": ...}` in JSON mode.
+    pub fn error(&self, out: &mut dyn Write, message: &str) -> io::Result<()> {
+        if self.json {
+            writeln!(out, "{}", serde_json::json!({ "error": message }))
+        } else {
+            writeln!(out, "{}{}{}", RED, message, RESET)
+        }
+    }
+
+    /// Writes a warning to `out`, as `{"warning": ...}` in JSON mode.
+    pub fn warning(&self, out: &mut dyn Write, message: &str) -> io::Result<()> {
+        if self.json {
+            writeln!(out, "{}", serde_json::json!({ "warning": message }))
+        } else {
+            writeln!(out, "{}{}{}", YELLOW, message, RESET)
+        }
+    }
+}
@@ -1,7 +1,57 @@
+use std::io::{self, Write};
+
 pub const RED: &str = "\x1b[31m";
 pub const GREEN: &str = "\x1b[32m";
 pub const YELLOW: &str = "\x1b[33m";
 pub const BLUE: &str = "\x1b[34m";
 pub const CYAN: &str = "\x1b[36m";
 pub const MAGENTA: &str = "\x1b[35m";
-pub const RESET: &str = "\x1b[0m";
\ No newline at end of file
+pub const RESET: &str = "\x1b[0m";
+
+// True when the NO_COLOR convention(https://no-color.org) asks for plain output: the variable is set and not empty.
+pub fn no_color_requested() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+// Writer that drops ANSI escape sequences(ESC, `[`, parameters, final letter) on their way to `inner`.
+// An escape split across writes is still dropped whole.
+pub struct StripAnsi<'a> {
+    inner: &'a mut dyn Write,
+    in_escape: bool,    // Inside an escape sequence, waiting for its final letter
+}
+
+impl<'a> StripAnsi<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        StripAnsi { inner, in_escape: false }
+    }
+}
+
+impl Write for StripAnsi<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut plain = Vec::with_capacity(buf.len());
+        for &byte in buf {
+            if self.in_escape {
+                self.in_escape = !byte.is_ascii_alphabetic();
+            } else if byte == 0x1b {
+                self.in_escape = true;
+            } else {
+                plain.push(byte);
+            }
+        }
+        self.inner.write_all(&plain)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Runs `write` against `out` as is, or with the colors stripped when `colors` is false.
+pub fn with_colors<R>(colors: bool, out: &mut dyn Write, write: impl FnOnce(&mut dyn Write) -> R) -> R {
+    if colors {
+        write(out)
+    } else {
+        write(&mut StripAnsi::new(out))
+    }
+}
@@ -1,20 +1,24 @@
 use uuid::Uuid;
-use chrono::{DateTime, Local};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use chrono::{DateTime, Utc};
 use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
 
-use crate::graph::GraphDb;
+use crate::graph::{GraphDb, RelationshipType};
 use crate::graph::fact::Fact;
-use crate::engine::utils::{sort_facts_by_time, deduplicate_facts};
+use crate::engine::utils::{sort_facts_by_time, deduplicate_facts_by_content, format_timestamp};
 
 
 /// Represents a logical grouping of related facts - a "case"
 /// Examples: investigation, narrative, or any related cluster of entities and facts
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Case {
     pub id: Uuid,                       // Unique identifies for the case
     pub name: String,                   // Human-readable case name/title
     pub description: String,            // Optional textual summary or notes about the case
-    pub created_at: DateTime<Local>,    // Timestamp of when the case was created
+    pub created_at: DateTime<Utc>,    // Timestamp of when the case was created
     pub related_entity_ids: Vec<Uuid>,  // List of Entity UUIDs involved in the case
     pub facts: Vec<Fact>,               // All Facts relevant to the case's entities
 }
@@ -26,8 +30,10 @@ pub struct CaseBuilder<'a> {
     db: &'a GraphDb,                // Reference to the graph database for querying entities & facts
     seed_entity_id: Uuid,           // Starting entity UUID to build the case around
     max_depth: usize,               // Maximum BFS traversal depth to collect related entities
-    from: Option<DateTime<Local>>,  // Optional lower bound on timestamp to filter facts
-    to: Option<DateTime<Local>>,    // Optional upper bound on timestamp to filter facts
+    from: Option<DateTime<Utc>>,  // Optional lower bound on timestamp to filter facts
+    to: Option<DateTime<Utc>>,    // Optional upper bound on timestamp to filter facts
+    allowed_relationship_types: Vec<String>, // Relationship types the BFS may cross (empty = all)
+    deterministic_id: bool,         // Derive the case id from seed, depth and name instead of a random v4
 }
 
 impl Case {
@@ -37,7 +43,7 @@ impl Case {
             id: Uuid::new_v4(),
             name: name.to_string(),
             description: description.to_string(),
-            created_at: Local::now(),
+            created_at: Utc::now(),
             related_entity_ids,
             facts,
         }
@@ -45,7 +51,7 @@ impl Case {
 
     /// Filter this case's facts by an optional time window
     /// Returns all facts whose timestamp falls withing the `[from, to]` range (inclusive)
-    pub fn filter_by_time(&self, from: Option<DateTime<Local>>, to: Option<DateTime<Local>>) -> Vec<Fact> {
+    pub fn filter_by_time(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Vec<Fact> {
         self.facts.iter()
             .filter(|fact| {
                 let ts = fact.timestamp();
@@ -59,6 +65,65 @@ impl Case {
             .collect()
     }
 
+    /// Explain why a fact belongs to this case: lists the related entities that pulled it in via `involves_any`.
+    /// The seed entity (first in `related_entity_ids`) is marked as such.
+    pub fn explain_fact_inclusion(&self, fact: &Fact) -> String {
+        let causes: Vec<String> = self.related_entity_ids.iter()
+            .enumerate()
+            .filter(|(_, id)| fact.involves_any(&[**id]))
+            .map(|(i, id)| if i == 0 { format!("{} (seed)", id) } else { id.to_string() })
+            .collect();
+
+        if causes.is_empty() {
+            return "Not included: the fact involves none of this case's related entities.".to_string();
+        }
+
+        let noun = if causes.len() == 1 { "entity" } else { "entities" };
+        if self.facts.contains(fact) {
+            format!("Included because it involves related {} {}", noun, causes.join(", "))
+        } else {
+            format!("Not included (outside the case's time range), although it involves related {} {}", noun, causes.join(", "))
+        }
+    }
+
+    /// Serialize the case (metadata, related entities and facts) as pretty-printed JSON,
+    /// so it can be saved and reopened without the graph it was built from.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Read back a case written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<Case> {
+        serde_json::from_str(json)
+    }
+
+    /// Render the case as a shareable Markdown report: title, creation time, related entities
+    /// (names and types resolved from `db`, labelled by `label_key` when given) and the facts in chronological order.
+    pub fn to_markdown(&self, db: &GraphDb, label_key: Option<&str>) -> String {
+        let mut md = format!("# {}\n\n", self.name);
+        md.push_str(&format!("*Created at {}*\n\n", format_timestamp(&self.created_at, false)));
+        if !self.description.is_empty() {
+            md.push_str(&format!("{}\n\n", self.description));
+        }
+
+        md.push_str(&format!("## Related Entities ({})\n\n", self.related_entity_ids.len()));
+        for id in &self.related_entity_ids {
+            match db.get_entity(id) {
+                Some(e) => md.push_str(&format!("- **{}** ({}) `{}`\n", e.display_name(label_key), e.entity_type.to_string(), id)),
+                None => md.push_str(&format!("- *Unknown* `{}`\n", id)),
+            }
+        }
+
+        // A case keeps its facts in the order it was built with, so that order breaks timestamp ties
+        let mut facts: Vec<(u64, &Fact)> = self.facts.iter().enumerate().map(|(i, fact)| (i as u64, fact)).collect();
+        sort_facts_by_time(&mut facts);
+        md.push_str(&format!("\n## Facts ({})\n\n", facts.len()));
+        for (_, fact) in facts {
+            md.push_str(&format!("- {}\n", format_fact(fact, db, false, label_key)));
+        }
+        md
+    }
+
     /// Check if a given entity UUID is involved in this case.
     /// Returns true if the entity is listed in related_entity_ids.
     pub fn involves_entity(&self, entity_id: &Uuid) -> bool {
@@ -77,6 +142,8 @@ impl<'a> CaseBuilder<'a> {
             max_depth: 2,
             from: None,
             to: None,
+            allowed_relationship_types: Vec::new(),
+            deterministic_id: false,
         }
     }
 
@@ -89,14 +156,34 @@ impl<'a> CaseBuilder<'a> {
 
     /// Set optional time window filter for facts included in the case.
     /// Facts outside the `[from, to]` range will be excluded.
-    pub fn with_time_range(mut self, from: Option<DateTime<Local>>, to: Option<DateTime<Local>>) -> Self {
+    pub fn with_time_range(mut self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Self {
         self.from = from;
         self.to = to;
         self
     }
 
+    /// Restrict BFS traversal to edges of the given relationship types (e.g. only "WorksAt").
+    /// An empty list means every relationship type may be crossed.
+    pub fn with_allowed_relationship_types(mut self, types: Vec<String>) -> Self {
+        self.allowed_relationship_types = types;
+        self
+    }
+
+    /// Typed form of `with_allowed_relationship_types`: only edges of these relationship types are traversed.
+    /// An empty list leaves traversal unrestricted.
+    pub fn with_relationship_types(self, types: Vec<RelationshipType>) -> Self {
+        self.with_allowed_relationship_types(types.iter().map(RelationshipType::to_string).collect())
+    }
+
+    /// Derive the case id as a UUID v5 of the seed entity id, max depth and case name,
+    /// so re-running the same build yields the same id and cases can be correlated across runs.
+    pub fn with_deterministic_id(mut self) -> Self {
+        self.deterministic_id = true;
+        self
+    }
+
     /// Perform breadth-first search (BFS) starting from the seed entity node
-    /// in the graph to collect all related entities up to max_depth.
+    /// over the undirected neighborhood in the graph to collect all related entities up to max_depth.
     ///
     /// Uses a HashSet to avoid duplicates and returns a Vec of unique entity UUIDs.
     fn collect_related_entities(&self) -> Vec<Uuid> {
@@ -121,11 +208,6 @@ impl<'a> CaseBuilder<'a> {
             // Begin BFS loop
             while let Some((node_idx, depth)) = queue.pop_front() {
 
-                // Skip nodes beyond the max_depth
-                if depth > self.max_depth {
-                    continue;
-                }
-
                 // Access the entity at this node index
                 if let Some(entity) = graph.node_weight(node_idx) {
 
@@ -135,9 +217,22 @@ impl<'a> CaseBuilder<'a> {
                         // Record the entity UUID
                         related.push(entity.id);
 
-                        // Enqueue all neighbors with incremented depth
-                        for neighbor in graph.neighbors(node_idx) {
-                            queue.push_back((neighbor, depth + 1));
+                        // Entities at max_depth are included but not expanded, so nothing beyond it is ever queued
+                        if depth == self.max_depth {
+                            continue;
+                        }
+
+                        // Enqueue all neighbors reachable over an allowed edge, with incremented depth.
+                        // Edges are followed both ways: a relationship's target is as much a part of the case as its source
+                        let outgoing = graph.edges_directed(node_idx, Direction::Outgoing).map(|edge| (edge.target(), edge));
+                        let incoming = graph.edges_directed(node_idx, Direction::Incoming).map(|edge| (edge.source(), edge));
+                        for (neighbor_idx, edge) in outgoing.chain(incoming) {
+                            let rel_type = edge.weight().relationship_type.to_string();
+                            if self.allowed_relationship_types.is_empty()
+                                || self.allowed_relationship_types.contains(&rel_type)
+                            {
+                                queue.push_back((neighbor_idx, depth + 1));
+                            }
                         }
                     }
                 }
@@ -161,8 +256,8 @@ impl<'a> CaseBuilder<'a> {
         // Filter event log facts that:
         // - Occur within time range (if set)
         // - Involve any of the related entities
-        let mut relevant_facts: Vec<Fact> = self.db.event_log.iter()
-            .filter(|fact| {
+        let mut relevant_facts: Vec<(u64, &Fact)> = self.db.sequenced_facts()
+            .filter(|(_, fact)| {
                 let ts = fact.timestamp();
 
                 // Check time range filter
@@ -172,61 +267,288 @@ impl<'a> CaseBuilder<'a> {
                 // Check if fact involves any of the related entities
                 in_time && fact.involves_any(&related_entities)
             })
-            .cloned()
             .collect();
 
         // Sort facts chronologically for consistency
         sort_facts_by_time(&mut relevant_facts);
 
-        // Remove duplicate facts (if any)
-        relevant_facts = deduplicate_facts(relevant_facts);
+        // Remove duplicate facts (if any), including the same fact recorded more than once
+        let relevant_facts = deduplicate_facts_by_content(relevant_facts.into_iter().map(|(_, fact)| fact.clone()).collect());
 
         // Create and return the final Case object
-        Case::new(name, description, related_entities, relevant_facts)
+        let mut case = Case::new(name, description, related_entities, relevant_facts);
+        if self.deterministic_id {
+            let key = format!("{}|{}", self.max_depth, name);
+            case.id = Uuid::new_v5(&self.seed_entity_id, key.as_bytes());
+        }
+        case
     }
 }
 
-pub fn display_case(case: &Case, db: &GraphDb) {
-    println!("=== 📦Case: {} ===", case.name);
-    println!("🆔 ID: {}", case.id);
-    println!("🕒 Created At: {}", case.created_at.format("%Y-%m-%d %H:%M:%S"));
-    println!("📝 Description: {}", case.description);
-    println!("🔗 Related Entities ({}):", case.related_entity_ids.len());
+/// Writes a case to `out`; `relative` shows timestamps as "3 days ago" instead of absolute dates,
+/// and `label_key` names entities by that property where they have it.
+pub fn display_case(out: &mut dyn Write, case: &Case, db: &GraphDb, relative: bool, label_key: Option<&str>) -> io::Result<()> {
+    writeln!(out, "=== 📦Case: {} ===", case.name)?;
+    writeln!(out, "🆔 ID: {}", case.id)?;
+    writeln!(out, "🕒 Created At: {}", format_timestamp(&case.created_at, relative))?;
+    writeln!(out, "📝 Description: {}", case.description)?;
+    writeln!(out, "🔗 Related Entities ({}):", case.related_entity_ids.len())?;
 
     for id in &case.related_entity_ids {
-        let label = db.graph.node_weights().find(|e| e.id == *id)
-            .map(|e| format!("{} ({:?})", e.name, e.entity_type))
+        let label = db.entities().find(|e| e.id == *id)
+            .map(|e| format!("{} ({})", e.display_name(label_key), e.entity_type.to_string()))
             .unwrap_or_else(|| "<Unknown>".to_string());
 
-        println!("  - {}: {}", id, label);
+        writeln!(out, "  - {}: {}", id, label)?;
     }
 
-    println!("\n📚 Facts ({}):", case.facts.len());
+    writeln!(out, "\n📚 Facts ({}):", case.facts.len())?;
 
     for fact in &case.facts {
-        match fact {
-           Fact::EntityCreated { entity_id, timestamp, .. } => {
-                //println!("  [CREATE] {} '{}' at {}", entity.id, entity.name, timestamp.format("%Y-%m-%d %H:%M:%S"));
-               println!("🆕  [CREATE] Entity {} at {}", entity_id, timestamp.format("%Y-%m-%d %H:%M:%S"));
-            }
-            Fact::EntityUpdated { entity_id, timestamp, .. } => {
-                println!("🔄  [UPDATE] Entity {} at {}", entity_id, timestamp.format("%Y-%m-%d %H:%M:%S"));
-            }
-            Fact::RelationshipAdded { source_id, target_id, relationship_type, timestamp, .. } => {
-                let source = db.graph.node_weights().find(|e| e.id == *source_id)
-                    .map(|e| e.name.clone()).unwrap_or_else(|| "Unknown".to_string());
-                let target = db.graph.node_weights().find(|e| e.id == *target_id)
-                    .map(|e| e.name.clone()).unwrap_or_else(|| "Unknown".to_string());
-                println!("🔗  [REL] {} --{}--> {} @ {}", source, relationship_type, target, timestamp.format("%Y-%m-%d %H:%M:%S"));
-            }
-            Fact::EntityDeleted {  entity_id, timestamp} => {
-                println!("❌  [DELETE] Entity {} at {}", entity_id, timestamp.format("%Y-%m-%d %H:%M:%S"));
-            }
-            Fact::RelationshipInvalidated { source_id, target_id, timestamp } => {
-                println!("🚫  [REL-INVALID] {} -> {} at {}", source_id, target_id, timestamp.format("%Y-%m-%d %H:%M:%S")); 
-            }
-        }
+        writeln!(out, "{}", format_fact(fact, db, relative, label_key))?;
     }
     
-    println!("===============================");
-}
\ No newline at end of file
+    writeln!(out, "===============================")?;
+
+    Ok(())
+}
+
+/// Formats a single fact as one human-readable line, resolving relationship endpoints to entity labels.
+/// Shared by `display_case` and the CLI commands that list facts.
+pub fn format_fact(fact: &Fact, db: &GraphDb, relative: bool, label_key: Option<&str>) -> String {
+    match fact {
+        Fact::EntityCreated { entity_id, timestamp, .. } => {
+            format!("🆕  [CREATE] Entity {} at {}", entity_id, format_timestamp(timestamp, relative))
+        }
+        Fact::EntityUpdated { entity_id, timestamp, .. } => {
+            format!("🔄  [UPDATE] Entity {} at {}", entity_id, format_timestamp(timestamp, relative))
+        }
+        Fact::RelationshipAdded { source_id, target_id, relationship_type, timestamp, .. } => {
+            let source = db.entities().find(|e| e.id == *source_id)
+                .map(|e| e.display_name(label_key)).unwrap_or_else(|| "Unknown".to_string());
+            let target = db.entities().find(|e| e.id == *target_id)
+                .map(|e| e.display_name(label_key)).unwrap_or_else(|| "Unknown".to_string());
+            format!("🔗  [REL] {} --{}--> {} @ {}", source, relationship_type, target, format_timestamp(timestamp, relative))
+        }
+        Fact::EntityDeleted { entity_id, timestamp } => {
+            format!("❌  [DELETE] Entity {} at {}", entity_id, format_timestamp(timestamp, relative))
+        }
+        Fact::EntityMerged { kept_id, removed_id, timestamp } => {
+            format!("🔀  [MERGE] Entity {} into {} at {}", removed_id, kept_id, format_timestamp(timestamp, relative))
+        }
+        Fact::RelationshipInvalidated { source_id, target_id, timestamp, relationship_type } => {
+            let scope = relationship_type.as_ref().map(|t| format!(" ({})", t)).unwrap_or_default();
+            format!("🚫  [REL-INVALID] {} -> {}{} at {}", source_id, target_id, scope, format_timestamp(timestamp, relative))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::fact::FactStore;
+    use crate::test_utils::{add_entities, relate};
+
+    #[test]
+    fn test_allowed_relationship_types_limit_expansion() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme", "London"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+        relate(&mut db, ids[1], RelationshipType::LocatedAt, ids[2]);
+
+        let unrestricted = CaseBuilder::new(&db, ids[0]).build("all", "");
+        assert_eq!(unrestricted.related_entity_ids.len(), 3);
+
+        let works_only = CaseBuilder::new(&db, ids[0])
+            .with_allowed_relationship_types(vec!["WorksAt".to_string()])
+            .build("works only", "");
+        assert_eq!(works_only.related_entity_ids, vec![ids[0], ids[1]]);
+    }
+
+    #[test]
+    fn test_explain_fact_inclusion_names_both_relationship_endpoints() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+
+        let case = CaseBuilder::new(&db, ids[0]).build("case", "");
+        let relationship = case.facts.iter()
+            .find(|f| matches!(f, Fact::RelationshipAdded { .. }))
+            .unwrap();
+
+        let explanation = case.explain_fact_inclusion(relationship);
+
+        assert!(explanation.starts_with("Included"));
+        assert!(explanation.contains(&format!("{} (seed)", ids[0])));
+        assert!(explanation.contains(&ids[1].to_string()));
+    }
+
+    #[test]
+    fn test_deterministic_id_is_stable_across_builds() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+
+        let first = CaseBuilder::new(&db, ids[0]).with_max_depth(2).with_deterministic_id().build("case", "");
+        let second = CaseBuilder::new(&db, ids[0]).with_max_depth(2).with_deterministic_id().build("case", "");
+        let deeper = CaseBuilder::new(&db, ids[0]).with_max_depth(3).with_deterministic_id().build("case", "");
+        let random = CaseBuilder::new(&db, ids[0]).with_max_depth(2).build("case", "");
+
+        assert_eq!(first.id, second.id);
+        assert_ne!(first.id, deeper.id);
+        assert_ne!(first.id, random.id);
+    }
+
+    #[test]
+    fn test_case_follows_incoming_edges_to_the_seed() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+
+        // Acme is only ever the target of the WorksAt edge
+        let case = CaseBuilder::new(&db, ids[1]).build("acme", "");
+
+        assert_eq!(case.related_entity_ids, vec![ids[1], ids[0]]);
+    }
+
+    #[test]
+    fn test_max_depth_bounds_included_entities_exactly() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["A", "B", "C", "D"]);
+        for pair in ids.windows(2) {
+            relate(&mut db, pair[0], RelationshipType::Knows, pair[1]);
+        }
+
+        let case = CaseBuilder::new(&db, ids[1]).with_max_depth(1).build("one hop", "");
+        let mut related = case.related_entity_ids.clone();
+        related.sort();
+        let mut expected = vec![ids[0], ids[1], ids[2]];
+        expected.sort();
+        assert_eq!(related, expected);
+
+        let case = CaseBuilder::new(&db, ids[0]).with_max_depth(2).build("two hops", "");
+        assert_eq!(case.related_entity_ids, vec![ids[0], ids[1], ids[2]]);
+    }
+
+    #[test]
+    fn test_with_relationship_types_skips_other_edges() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme", "London"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+        relate(&mut db, ids[0], RelationshipType::LocatedAt, ids[2]);
+
+        let case = CaseBuilder::new(&db, ids[0])
+            .with_relationship_types(vec![RelationshipType::WorksAt])
+            .build("work", "");
+
+        assert_eq!(case.related_entity_ids, vec![ids[0], ids[1]]);
+    }
+
+    #[test]
+    fn test_case_json_round_trip() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+
+        let case = CaseBuilder::new(&db, ids[0]).with_max_depth(0).build("john", "just John");
+        assert_eq!(case.facts.len(), 2);
+
+        let reopened = Case::from_json(&case.to_json().unwrap()).unwrap();
+
+        assert_eq!(reopened.id, case.id);
+        assert_eq!(reopened.name, "john");
+        assert_eq!(reopened.related_entity_ids, case.related_entity_ids);
+        assert_eq!(reopened.facts, case.facts);
+        assert_eq!(reopened.created_at, case.created_at);
+    }
+
+    #[test]
+    fn test_markdown_report_lists_name_and_entities() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        relate(&mut db, ids[0], RelationshipType::WorksAt, ids[1]);
+
+        let case = CaseBuilder::new(&db, ids[0]).build("Acme payroll", "");
+        let md = case.to_markdown(&db, None);
+
+        assert!(md.starts_with("# Acme payroll\n"));
+        let entity_bullets = md.lines().filter(|l| l.starts_with("- **")).count();
+        assert_eq!(entity_bullets, case.related_entity_ids.len());
+        assert!(md.contains("- **John** ("));
+        assert_eq!(md.matches("\n- ").count(), case.related_entity_ids.len() + case.facts.len());
+    }
+
+    #[test]
+    fn test_build_collapses_same_edge_recorded_twice() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        let first = Utc::now() - chrono::Duration::seconds(1);
+        let works_at = Fact::RelationshipAdded {
+            source_id: ids[0],
+            target_id: ids[1],
+            relationship_type: RelationshipType::WorksAt.to_string(),
+            timestamp: first,
+            valid_from: 2020,
+            valid_to: None,
+        };
+        db.add_fact(FactStore { facts: vec![works_at.clone(), works_at.with_timestamp(first + chrono::Duration::seconds(1))] });
+
+        let case = CaseBuilder::new(&db, ids[0]).build("john", "");
+
+        let edges: Vec<&Fact> = case.facts.iter().filter(|f| matches!(f, Fact::RelationshipAdded { .. })).collect();
+        assert_eq!(edges, vec![&works_at]);
+    }
+
+    #[test]
+    fn test_build_keeps_edge_re_added_after_invalidation() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        let start = Utc::now() - chrono::Duration::seconds(3);
+        let works_at = Fact::RelationshipAdded {
+            source_id: ids[0],
+            target_id: ids[1],
+            relationship_type: RelationshipType::WorksAt.to_string(),
+            timestamp: start,
+            valid_from: 2020,
+            valid_to: None,
+        };
+        let ended = Fact::RelationshipInvalidated {
+            source_id: ids[0],
+            target_id: ids[1],
+            timestamp: start + chrono::Duration::seconds(1),
+            relationship_type: Some(RelationshipType::WorksAt.to_string()),
+        };
+        let rehired = works_at.with_timestamp(start + chrono::Duration::seconds(2));
+        db.add_fact(FactStore { facts: vec![works_at.clone(), ended.clone(), rehired.clone()] });
+
+        let case = CaseBuilder::new(&db, ids[0]).build("john", "");
+
+        let history: Vec<&Fact> = case.facts.iter().filter(|f| f.involves_any(&[ids[0]]) && f.involves_any(&[ids[1]])).collect();
+        assert_eq!(history, vec![&works_at, &ended, &rehired]);
+    }
+
+    #[test]
+    fn test_case_and_timeline_agree_on_window_across_timezones() {
+        use crate::engine::timeline::{generate_timeline, TimelineQuery};
+        use crate::engine::utils::parse_date;
+
+        // 23:30 on the 15th in New York is already the 16th in UTC; 01:00 on the 16th in Berlin is still the 15th
+        let entity_id = Uuid::new_v4();
+        let fact_at = |timestamp: &str| -> Fact {
+            serde_json::from_str(&format!(r#"{{"EntityDeleted": {{"entity_id": "{}", "timestamp": "{}"}}}}"#, entity_id, timestamp)).unwrap()
+        };
+        let inside = fact_at("2024-03-15T23:30:00-05:00");
+        let outside = fact_at("2024-03-16T01:00:00+02:00");
+
+        let mut db = GraphDb::new();
+        db.event_log = vec![inside.clone(), outside.clone()];
+        let case = Case::new("boundary", "", vec![entity_id], db.event_log.clone());
+
+        let (from, to) = (parse_date("2024-03-16"), parse_date("2024-03-17"));
+        let from_case = case.filter_by_time(from, to);
+        let from_timeline = generate_timeline(&db, &TimelineQuery { from, to, ..Default::default() }).facts;
+
+        assert_eq!(from_case, vec![inside]);
+        assert_eq!(from_timeline, from_case);
+    }
+}
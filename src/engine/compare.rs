@@ -0,0 +1,112 @@
+use serde::Serialize;
+
+use crate::graph::Entity;
+
+/// Side-by-side comparison of two entities' properties.
+/// - `shared`: keys present on both with the same value
+/// - `differing`: keys present on both with different values, as `(key, a_value, b_value)`
+/// - `only_in_a` / `only_in_b`: keys unique to one side
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct EntityComparison {
+    pub shared: Vec<(String, String)>,
+    pub differing: Vec<(String, String, String)>,
+    pub only_in_a: Vec<(String, String)>,
+    pub only_in_b: Vec<(String, String)>,
+}
+
+/// Compares the property maps of two entities.
+/// Since properties are stored in a `BTreeMap`, every list comes back sorted by key.
+pub fn compare_entities(a: &Entity, b: &Entity) -> EntityComparison {
+    let mut comparison = EntityComparison::default();
+
+    for (key, a_value) in &a.properties {
+        match b.properties.get(key) {
+            Some(b_value) if b_value == a_value => {
+                comparison.shared.push((key.clone(), a_value.clone()));
+            }
+            Some(b_value) => {
+                comparison.differing.push((key.clone(), a_value.clone(), b_value.clone()));
+            }
+            None => comparison.only_in_a.push((key.clone(), a_value.clone())),
+        }
+    }
+
+    // Anything in b that a never mentioned
+    for (key, b_value) in &b.properties {
+        if !a.properties.contains_key(key) {
+            comparison.only_in_b.push((key.clone(), b_value.clone()));
+        }
+    }
+
+    comparison
+}
+
+impl EntityComparison {
+    /// Renders the comparison as an aligned three-column table: property, first entity, second entity.
+    pub fn to_table(&self, a_label: &str, b_label: &str) -> String {
+        let mut rows: Vec<(String, String, String)> = Vec::new();
+        for (key, value) in &self.shared {
+            rows.push((key.clone(), value.clone(), value.clone()));
+        }
+        for (key, a_value, b_value) in &self.differing {
+            rows.push((format!("{} *", key), a_value.clone(), b_value.clone()));
+        }
+        for (key, value) in &self.only_in_a {
+            rows.push((key.clone(), value.clone(), "-".to_string()));
+        }
+        for (key, value) in &self.only_in_b {
+            rows.push((key.clone(), "-".to_string(), value.clone()));
+        }
+
+        // Size each column to its widest cell (header included)
+        let key_width = rows.iter().map(|r| r.0.chars().count()).chain(["property".len()]).max().unwrap_or(0);
+        let a_width = rows.iter().map(|r| r.1.chars().count()).chain([a_label.chars().count()]).max().unwrap_or(0);
+
+        let mut lines = vec![format!("{:<kw$}  {:<aw$}  {}", "property", a_label, b_label, kw = key_width, aw = a_width)];
+        lines.push(format!("{}  {}  {}", "-".repeat(key_width), "-".repeat(a_width), "-".repeat(b_label.chars().count().max(1))));
+        for (key, a_value, b_value) in rows {
+            lines.push(format!("{:<kw$}  {:<aw$}  {}", key, a_value, b_value, kw = key_width, aw = a_width));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use uuid::Uuid;
+    use crate::graph::EntityType;
+
+    fn person(name: &str, props: &[(&str, &str)]) -> Entity {
+        let mut properties = BTreeMap::new();
+        properties.insert("name".to_string(), name.to_string());
+        for (k, v) in props {
+            properties.insert(k.to_string(), v.to_string());
+        }
+        Entity { id: Uuid::new_v4(), name: name.to_string(), entity_type: EntityType::Person, properties, aliases: Vec::new() }
+    }
+
+    #[test]
+    fn test_compare_entities_splits_shared_differing_and_unique() {
+        let a = person("John", &[("city", "London"), ("employer", "Acme"), ("email", "j@acme.com")]);
+        let b = person("Jon", &[("city", "London"), ("employer", "Widgets"), ("phone", "555")]);
+
+        let comparison = compare_entities(&a, &b);
+
+        assert_eq!(comparison.shared, vec![("city".to_string(), "London".to_string())]);
+        assert_eq!(
+            comparison.differing,
+            vec![
+                ("employer".to_string(), "Acme".to_string(), "Widgets".to_string()),
+                ("name".to_string(), "John".to_string(), "Jon".to_string()),
+            ]
+        );
+        assert_eq!(comparison.only_in_a, vec![("email".to_string(), "j@acme.com".to_string())]);
+        assert_eq!(comparison.only_in_b, vec![("phone".to_string(), "555".to_string())]);
+
+        let table = comparison.to_table("John", "Jon");
+        assert!(table.lines().all(|line| !line.is_empty()));
+        assert_eq!(table.lines().count(), 2 + 5);
+    }
+}
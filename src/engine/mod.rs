@@ -1,8 +1,10 @@
 pub mod case;
+pub mod compare;
 pub mod search;
 pub mod timeline;
 pub mod utils;
 
-pub use search::{SearchQuery, search_entities};
-pub use timeline::{generate_timeline, TimelineQuery, TimelineResult};
-pub use case::{Case, CaseBuilder};
\ No newline at end of file
+pub use search::{parse_query, SearchQuery, SortKey, search_entities};
+pub use timeline::{generate_timeline, timeline_to_named_json, TimelineEntry, TimelineMarker, TimelineQuery, TimelineResult};
+pub use case::{Case, CaseBuilder};
+pub use compare::{compare_entities, EntityComparison};
\ No newline at end of file
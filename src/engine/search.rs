@@ -1,11 +1,79 @@
+use std::cmp::Reverse;
+use std::str::FromStr;
+use regex::Regex;
 use crate::graph::{GraphDb, Entity, EntityType};
 
+/// How search results should be ordered
+/// - `Recency`: Most recently active entities first (by their latest fact); entities with no facts last
+/// - `Name`: Alphabetical by name
+/// - `Type`: Grouped by entity type (alphabetical), then by name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Recency,
+    Name,
+    Type,
+}
+
 /// Define the structure for a search query
 /// - `entity_type`: Optional filter to match entities of a specific type
 /// - `name_contains`: Optional substring to search for in entity names
+/// - `name_regex`: Optional pattern entity names must match (e.g. `.+@acme\.com`)
+/// - `property_equals`: Optional `(key, value)` pair the entity's properties must contain
+/// - `sort_by`: Optional ordering of the results; without one, results stay in node-index order
+/// - `offset`: Number of (sorted) results to skip, for paging
+/// - `limit`: Optional maximum number of results returned after the offset
+#[derive(Default)]
 pub struct SearchQuery {
     pub entity_type: Option<EntityType>,
     pub name_contains: Option<String>,
+    pub name_regex: Option<Regex>,
+    pub property_equals: Option<(String, String)>,
+    pub sort_by: Option<SortKey>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// Parses the query mini-syntax into a `SearchQuery`.
+/// - `type:<EntityType>` filters by entity type
+/// - `name:<text>` and any bare words filter by name substring (joined with single spaces)
+/// - `regex:<pattern>` filters by a regular expression over the name
+/// - `<key>=<value>` filters by an exact property value (e.g. `country=US`)
+/// - `sort:recent`, `sort:name` or `sort:type` orders the results (unsorted otherwise)
+///
+/// # Returns
+/// - An error message naming the offending value when `type:` isn't a known entity type, `regex:` doesn't compile
+///   or `sort:` isn't a known order
+pub fn parse_query(input: &str) -> Result<SearchQuery, String> {
+    let mut query = SearchQuery::default();
+    let mut name_parts = Vec::new();
+
+    for token in input.split_whitespace() {
+        if let Some(type_name) = token.strip_prefix("type:") {
+            let etype = EntityType::from_str(type_name).map_err(|_| format!("Unknown entity type: {}", type_name))?;
+            query.entity_type = Some(etype);
+        } else if let Some(pattern) = token.strip_prefix("regex:") {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex '{}': {}", pattern, e))?;
+            query.name_regex = Some(regex);
+        } else if let Some(order) = token.strip_prefix("sort:") {
+            query.sort_by = Some(match order {
+                "recent" => SortKey::Recency,
+                "name" => SortKey::Name,
+                "type" => SortKey::Type,
+                _ => return Err(format!("Unknown sort order '{}' (use recent, name or type)", order)),
+            });
+        } else if let Some(name) = token.strip_prefix("name:") {
+            name_parts.push(name);
+        } else if let Some((key, value)) = token.split_once('=').filter(|(key, _)| !key.is_empty()) {
+            query.property_equals = Some((key.to_string(), value.to_string()));
+        } else {
+            name_parts.push(token);
+        }
+    }
+
+    if !name_parts.is_empty() {
+        query.name_contains = Some(name_parts.join(" "));
+    }
+    Ok(query)
 }
 
 /// Search for entities in the graph that match the given query.
@@ -18,7 +86,7 @@ pub struct SearchQuery {
 /// # Returns
 /// - A list of references to entities that match all provided filters
 pub fn search_entities(db: &GraphDb, query: SearchQuery) -> Vec<&Entity> {
-    db.graph
+    let mut results: Vec<&Entity> = db.graph
         // Iterate over all node indices (each node represents an Entity)
         .node_indices()
         
@@ -34,13 +102,175 @@ pub fn search_entities(db: &GraphDb, query: SearchQuery) -> Vec<&Entity> {
                 matches &= &entity.entity_type == etype;
             }
             
+            // Name filters are satisfied by the entity's name or any of its aliases
+            let names = || std::iter::once(&entity.name).chain(&entity.aliases);
+
             // If a name filter is provided, check if the entity's name contains the substring
             if let Some(ref name_substr) = query.name_contains {
-                matches &= entity.name.contains(name_substr);
+                matches &= names().any(|name| name.contains(name_substr));
+            }
+
+            // If a name pattern is provided, check if the entity's name matches it
+            if let Some(ref name_regex) = query.name_regex {
+                matches &= names().any(|name| name_regex.is_match(name));
+            }
+
+            // If a property filter is provided, check the entity holds exactly that value
+            if let Some((ref key, ref value)) = query.property_equals {
+                matches &= entity.properties.get(key) == Some(value);
             }
             // Entity passes all filter conditions
             matches
         })
         // Collect all matching entities into a Vec
+        .collect();
+
+    // Apply the requested ordering (stable, so equal keys keep node-index order)
+    match query.sort_by {
+        // Reverse puts the latest timestamps first; `None` (no facts) sorts below any timestamp, so it lands last
+        Some(SortKey::Recency) => results.sort_by_key(|entity| Reverse(db.last_activity(&entity.id))),
+        Some(SortKey::Name) => results.sort_by(|a, b| a.name.cmp(&b.name)),
+        Some(SortKey::Type) => results.sort_by(|a, b| {
+            a.entity_type.to_string().cmp(&b.entity_type.to_string()).then_with(|| a.name.cmp(&b.name))
+        }),
+        None => {}
+    }
+
+    // Page through the ordered results
+    results
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
         .collect()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+    use crate::graph::fact::{Fact, FactStore};
+    use crate::test_utils::add_entities;
+
+    #[test]
+    fn test_recency_sort_puts_most_recent_activity_first() {
+        let mut db = GraphDb::new();
+        let (old, recent) = (Uuid::new_v4(), Uuid::new_v4());
+        let long_ago = Utc::now() - Duration::days(10);
+
+        let facts = [(old, "John Old"), (recent, "John Recent")]
+            .iter()
+            .map(|(id, name)| {
+                let mut properties = BTreeMap::new();
+                properties.insert("name".to_string(), name.to_string());
+                Fact::EntityCreated { entity_id: *id, timestamp: long_ago, properties }
+            })
+            .collect();
+        db.add_fact(FactStore { facts });
+
+        // Only one of them sees new activity
+        let mut updated_properties = BTreeMap::new();
+        updated_properties.insert("phone".to_string(), "555".to_string());
+        db.add_fact(FactStore {
+            facts: vec![Fact::EntityUpdated { entity_id: recent, timestamp: Utc::now(), updated_properties }],
+        });
+
+        let query = SearchQuery {
+            name_contains: Some("John".to_string()),
+            sort_by: Some(SortKey::Recency),
+            ..Default::default()
+        };
+        let results = search_entities(&db, query);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, recent);
+        assert_eq!(results[1].id, old);
+    }
+
+    #[test]
+    fn test_parse_query_builds_type_and_name_filters() {
+        let query = parse_query("type:Company").unwrap();
+        assert_eq!(query.entity_type, Some(EntityType::Company));
+        assert_eq!(query.name_contains, None);
+
+        let query = parse_query("type:Person name:John").unwrap();
+        assert_eq!(query.entity_type, Some(EntityType::Person));
+        assert_eq!(query.name_contains, Some("John".to_string()));
+
+        let query = parse_query("name:John").unwrap();
+        assert_eq!(query.entity_type, None);
+        assert_eq!(query.name_contains, Some("John".to_string()));
+
+        let query = parse_query("type:Spaceship").unwrap();
+        assert_eq!(query.entity_type, Some(EntityType::Custom("Spaceship".to_string())));
+        assert!(parse_query("type:").is_err());
+    }
+
+    #[test]
+    fn test_name_regex_filters_names() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John Doe", "Johnny", "Mr John"]);
+
+        let query = SearchQuery { name_regex: Some(Regex::new("^John").unwrap()), ..Default::default() };
+        let mut names: Vec<&str> = search_entities(&db, query).iter().map(|e| e.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["John Doe", "Johnny"]);
+
+        // Composes with the substring filter
+        let query = parse_query("regex:^John Doe").unwrap();
+        let names: Vec<&str> = search_entities(&db, query).iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["John Doe"]);
+
+        assert!(parse_query("regex:(").is_err());
+    }
+
+    #[test]
+    fn test_property_equals_matches_custom_property() {
+        let mut db = GraphDb::new();
+        let (us, uk) = (Uuid::new_v4(), Uuid::new_v4());
+        let facts = [(us, "Acme", "US"), (uk, "Widgets", "UK")]
+            .iter()
+            .map(|(id, name, country)| {
+                let mut properties = BTreeMap::new();
+                properties.insert("name".to_string(), name.to_string());
+                properties.insert("country".to_string(), country.to_string());
+                Fact::EntityCreated { entity_id: *id, timestamp: Utc::now(), properties }
+            })
+            .collect();
+        db.add_fact(FactStore { facts });
+
+        let query = SearchQuery { property_equals: Some(("country".to_string(), "US".to_string())), ..Default::default() };
+        let results = search_entities(&db, query);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, us);
+
+        let query = parse_query("country=UK").unwrap();
+        assert_eq!(query.property_equals, Some(("country".to_string(), "UK".to_string())));
+        assert_eq!(search_entities(&db, query)[0].id, uk);
+    }
+
+    #[test]
+    fn test_parse_query_sorts_only_when_asked() {
+        assert_eq!(parse_query("John").unwrap().sort_by, None);
+
+        let query = parse_query("sort:recent John").unwrap();
+        assert_eq!(query.sort_by, Some(SortKey::Recency));
+        assert_eq!(query.name_contains, Some("John".to_string()));
+
+        assert_eq!(parse_query("sort:name").unwrap().sort_by, Some(SortKey::Name));
+        assert!(parse_query("sort:oldest").is_err());
+    }
+
+    #[test]
+    fn test_offset_and_limit_page_through_sorted_results() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["Carol", "Alice", "Bob"]);
+
+        let query = SearchQuery { sort_by: Some(SortKey::Name), offset: 1, limit: Some(1), ..Default::default() };
+        let results = search_entities(&db, query);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "Bob");
+    }
+}
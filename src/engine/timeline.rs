@@ -1,4 +1,6 @@
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::graph::fact::Fact;
@@ -8,18 +10,68 @@ use crate::graph::GraphDb;
 /// - `entity_id`: Restrict results to the facts involving this entity
 /// - `from`: Optional lower bound on the timestamp (inclusive)
 /// - `to`: Optional upper bound on the timestamp (inclusive)
-#[derive(Debug)]
+/// - `property_key`: Only keep entity creations/updates that touch this property key
+/// - `validity_aware`: Place relationships on `entries` by their validity window instead of when they were recorded
+/// - `order`: Oldest first (the default) or newest first
+/// - `relationship_types`: Only keep relationship facts of these types; entity facts drop out while it is set
+#[derive(Debug, Default)]
 pub struct TimelineQuery {
     pub entity_id: Option<Uuid>,             // Optional filter: a specific entity
     pub from: Option<DateTime<Utc>>,         // Optional start time
     pub to: Option<DateTime<Utc>>,           // Optional end time
+    pub property_key: Option<String>,        // Optional filter: facts touching this property
+    pub validity_aware: bool,                // Reconstruct relationship lifespans from valid_from/valid_to
+    pub order: SortOrder,                    // Direction of the time ordering
+    pub relationship_types: Option<Vec<String>>, // Optional filter: relationship facts of these types
+}
+
+/// Direction in which timeline results are ordered by time.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Why a fact appears at a given point on the timeline
+/// - `Recorded`: When the fact was logged
+/// - `ValidFrom`: Start of a relationship's validity (January 1st of `valid_from`)
+/// - `ValidUntil`: Synthetic end marker for a relationship (January 1st of `valid_to`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineMarker {
+    Recorded,
+    ValidFrom,
+    ValidUntil,
+}
+
+/// One point on the timeline: a fact placed at `at` for the reason given by `marker`.
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    pub at: DateTime<Utc>,
+    pub marker: TimelineMarker,
+    pub fact: Fact,
 }
 
 /// Result of a timeline query
-/// - Contains all relevant facts, sorted by timestamp.
+/// - `facts`: All relevant facts, sorted by timestamp.
+/// - `entries`: The same facts as timeline points in chronological order. Without `validity_aware` these mirror
+///   `facts`; with it, relationship additions sit at their `valid_from` year and get a `ValidUntil` marker when they end.
 #[derive(Debug)]
 pub struct TimelineResult {
     pub facts: Vec<Fact>,
+    pub entries: Vec<TimelineEntry>,
+}
+
+impl TimelineResult {
+    /// Buckets `facts` by the UTC calendar day they were recorded on, for rendering day headers.
+    /// Facts keep their timeline order within each day.
+    pub fn group_by_day(&self) -> BTreeMap<NaiveDate, Vec<&Fact>> {
+        let mut days: BTreeMap<NaiveDate, Vec<&Fact>> = BTreeMap::new();
+        for fact in &self.facts {
+            days.entry(fact.timestamp().date_naive()).or_default().push(fact);
+        }
+        days
+    }
 }
 
 /// Extracts a filtered and time-ordered list of facts from the event log.
@@ -27,18 +79,18 @@ pub struct TimelineResult {
 /// This function:
 /// 1. Iterates through all facts in the event log
 /// 2. Filters them based on entity ID and time window (if specified)
-/// 3. Sorts the matching facts chronologically (oldest first)
+/// 3. Sorts the matching facts chronologically (oldest first, or newest first for `SortOrder::Descending`)
 /// 
 /// # Arguments
 /// - `db`: References to `GraphDb` that holds the event log.
 /// - `query`: Filtering criteria for entity and time range.
 /// 
 /// # Returns
-/// - A `TimelineResult` with matching facts in ascending timestamp order.
+/// - A `TimelineResult` with matching facts in the requested timestamp order.
 pub fn generate_timeline(db: &GraphDb, query: &TimelineQuery) -> TimelineResult {
     let mut relevant_facts = Vec::new();
 
-    for fact in &db.event_log {
+    for fact in db.facts() {
         
         // Match entity-specific facts
         let is_relevant = match fact {
@@ -54,7 +106,7 @@ pub fn generate_timeline(db: &GraphDb, query: &TimelineQuery) -> TimelineResult
 
             // Match relationship-specific facts (added or invalidated)
             Fact::RelationshipAdded { source_id, target_id, timestamp, .. }
-            | Fact::RelationshipInvalidated { source_id, target_id, timestamp } => {
+            | Fact::RelationshipInvalidated { source_id, target_id, timestamp, .. } => {
                 
                 // Check if either end of the relationship matches the entity ID (if provided), and timestamp falls within the query range
                 let involves_entity = query.entity_id.map_or(true, |id| id == *source_id || id == *target_id);
@@ -62,16 +114,339 @@ pub fn generate_timeline(db: &GraphDb, query: &TimelineQuery) -> TimelineResult
                     && query.to.map_or(true, |to| *timestamp <= to);
                 involves_entity && in_time_window
             }
+
+            // A merge concerns both the surviving and the absorbed entity
+            Fact::EntityMerged { kept_id, removed_id, timestamp } => {
+                query.entity_id.is_none_or(|id| id == *kept_id || id == *removed_id) &&
+                    query.from.is_none_or(|from| *timestamp >= from) &&
+                    query.to.is_none_or(|to| *timestamp <= to)
+            }
+        };
+
+        // When a property key is requested, keep only creations/updates whose property map has it.
+        // Deletions and relationship facts carry no properties, so they drop out.
+        let touches_property = match &query.property_key {
+            None => true,
+            Some(key) => match fact {
+                Fact::EntityCreated { properties, .. } => properties.contains_key(key),
+                Fact::EntityUpdated { updated_properties, .. } => updated_properties.contains_key(key),
+                _ => false,
+            },
+        };
+
+        // When relationship types are requested, keep only relationship facts of those types.
+        // An invalidation without a type removes edges of every type, so it matches any filter;
+        // entity creations, updates, deletions and merges drop out.
+        let has_relationship_type = match &query.relationship_types {
+            None => true,
+            Some(types) => match fact {
+                Fact::RelationshipAdded { relationship_type, .. } => types.contains(relationship_type),
+                Fact::RelationshipInvalidated { relationship_type, .. } => {
+                    relationship_type.as_ref().is_none_or(|t| types.contains(t))
+                }
+                _ => false,
+            },
         };
 
         // Collect all facts that match the filter
-        if is_relevant {
+        if is_relevant && touches_property && has_relationship_type {
             relevant_facts.push(fact.clone());
         }
     }
 
-    // Sort the filtered facts in ascending order by timestamp.
+    // Sort the filtered facts by timestamp; descending is the ascending order reversed,
+    // so facts sharing a timestamp come out newest-recorded first as well
     relevant_facts.sort_by_key(|fact| fact.timestamp());
+    if query.order == SortOrder::Descending {
+        relevant_facts.reverse();
+    }
+
+    let mut entries = Vec::new();
+    for fact in &relevant_facts {
+        match fact {
+            Fact::RelationshipAdded { valid_from, valid_to, .. } if query.validity_aware => {
+                // Fall back to the recording time if the year is out of chrono's range
+                let start = year_start(*valid_from).unwrap_or_else(|| fact.timestamp());
+                entries.push(TimelineEntry { at: start, marker: TimelineMarker::ValidFrom, fact: fact.clone() });
+                if let Some(end) = valid_to.and_then(year_start) {
+                    entries.push(TimelineEntry { at: end, marker: TimelineMarker::ValidUntil, fact: fact.clone() });
+                }
+            }
+            _ => entries.push(TimelineEntry { at: fact.timestamp(), marker: TimelineMarker::Recorded, fact: fact.clone() }),
+        }
+    }
+    // Stable, so a start marker stays ahead of an end marker in the same year (behind it when descending)
+    entries.sort_by_key(|entry| entry.at);
+    if query.order == SortOrder::Descending {
+        entries.reverse();
+    }
+
+    TimelineResult { facts: relevant_facts, entries }
+}
+
+/// Midnight UTC on January 1st of `year`, as used for relationship validity bounds.
+fn year_start(year: i64) -> Option<DateTime<Utc>> {
+    Utc.with_ymd_and_hms(i32::try_from(year).ok()?, 1, 1, 0, 0, 0).single()
+}
+
+/// Exports a timeline as a JSON array for reports, with every UUID paired with a readable name.
+///
+/// Each fact becomes an object with `kind` and `timestamp`, plus `{ "id", "name" }` objects for the
+/// entity (or relationship `source`/`target`) it involves, labelled by `label_key` when given.
+/// Entities no longer in the graph are named by their UUID string.
+pub fn timeline_to_named_json(result: &TimelineResult, db: &GraphDb, label_key: Option<&str>) -> String {
+    let named = |id: &Uuid| {
+        let name = db.get_entity(id).map(|e| e.display_name(label_key)).unwrap_or_else(|| id.to_string());
+        json!({ "id": id, "name": name })
+    };
+
+    let entries: Vec<Value> = result.facts.iter().map(|fact| match fact {
+        Fact::EntityCreated { entity_id, timestamp, properties } => json!({
+            "kind": "EntityCreated",
+            "timestamp": timestamp,
+            "entity": named(entity_id),
+            "properties": properties,
+        }),
+        Fact::EntityUpdated { entity_id, timestamp, updated_properties } => json!({
+            "kind": "EntityUpdated",
+            "timestamp": timestamp,
+            "entity": named(entity_id),
+            "properties": updated_properties,
+        }),
+        Fact::EntityDeleted { entity_id, timestamp } => json!({
+            "kind": "EntityDeleted",
+            "timestamp": timestamp,
+            "entity": named(entity_id),
+        }),
+        Fact::RelationshipAdded { source_id, target_id, relationship_type, timestamp, valid_from, valid_to } => json!({
+            "kind": "RelationshipAdded",
+            "timestamp": timestamp,
+            "source": named(source_id),
+            "target": named(target_id),
+            "relationship_type": relationship_type,
+            "valid_from": valid_from,
+            "valid_to": valid_to,
+        }),
+        Fact::RelationshipInvalidated { source_id, target_id, timestamp, relationship_type } => json!({
+            "kind": "RelationshipInvalidated",
+            "timestamp": timestamp,
+            "source": named(source_id),
+            "target": named(target_id),
+            "relationship_type": relationship_type,
+        }),
+        Fact::EntityMerged { kept_id, removed_id, timestamp } => json!({
+            "kind": "EntityMerged",
+            "timestamp": timestamp,
+            "kept": named(kept_id),
+            "removed": named(removed_id),
+        }),
+    }).collect();
+
+    serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+    use crate::graph::fact::FactStore;
+
+    fn props(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_property_key_filter_keeps_only_matching_facts() {
+        let mut db = GraphDb::new();
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp, properties: props(&[("name", "John"), ("address", "1 Main St")]) },
+                Fact::EntityCreated { entity_id: acme, timestamp, properties: props(&[("name", "Acme")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp, updated_properties: props(&[("phone", "555")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp, updated_properties: props(&[("address", "2 High St")]) },
+                Fact::RelationshipAdded {
+                    source_id: john,
+                    target_id: acme,
+                    relationship_type: "WorksAt".to_string(),
+                    timestamp,
+                    valid_from: 2020,
+                    valid_to: None,
+                },
+            ],
+        });
 
-    TimelineResult { facts: relevant_facts }
+        let query = TimelineQuery { property_key: Some("address".to_string()), ..Default::default() };
+        let result = generate_timeline(&db, &query);
+
+        assert_eq!(result.facts.len(), 2);
+        assert!(matches!(result.facts[0], Fact::EntityCreated { entity_id, .. } if entity_id == john));
+        assert!(matches!(&result.facts[1], Fact::EntityUpdated { updated_properties, .. } if updated_properties["address"] == "2 High St"));
+
+        // Without the filter everything is included
+        assert_eq!(generate_timeline(&db, &TimelineQuery::default()).facts.len(), 5);
+    }
+
+    #[test]
+    fn test_group_by_day_buckets_facts_by_utc_date() {
+        let mut db = GraphDb::new();
+        let john = Uuid::new_v4();
+        let at = |day, hour| Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap();
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp: at(15, 9), properties: props(&[("name", "John")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp: at(15, 23), updated_properties: props(&[("phone", "555")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp: at(16, 0), updated_properties: props(&[("city", "London")]) },
+            ],
+        });
+
+        let result = generate_timeline(&db, &TimelineQuery::default());
+        let days = result.group_by_day();
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[&NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()].len(), 2);
+        assert_eq!(days[&NaiveDate::from_ymd_opt(2024, 3, 16).unwrap()].len(), 1);
+    }
+
+    #[test]
+    fn test_descending_order_puts_latest_fact_first() {
+        let mut db = GraphDb::new();
+        let john = Uuid::new_v4();
+        let at = |day| Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp: at(1), properties: props(&[("name", "John")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp: at(20), updated_properties: props(&[("city", "London")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp: at(10), updated_properties: props(&[("phone", "555")]) },
+            ],
+        });
+
+        let result = generate_timeline(&db, &TimelineQuery { order: SortOrder::Descending, ..Default::default() });
+
+        assert_eq!(result.facts[0].timestamp(), at(20));
+        assert_eq!(result.facts.last().unwrap().timestamp(), at(1));
+        assert_eq!(result.entries[0].at, at(20));
+    }
+
+    #[test]
+    fn test_descending_order_is_ascending_reversed_for_equal_timestamps() {
+        let mut db = GraphDb::new();
+        let john = Uuid::new_v4();
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp: at, properties: props(&[("name", "John")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp: at, updated_properties: props(&[("city", "London")]) },
+            ],
+        });
+
+        let ascending = generate_timeline(&db, &TimelineQuery::default());
+        let mut descending = generate_timeline(&db, &TimelineQuery { order: SortOrder::Descending, ..Default::default() });
+        descending.facts.reverse();
+
+        assert_eq!(descending.facts, ascending.facts);
+    }
+
+    #[test]
+    fn test_relationship_type_filter_keeps_only_matching_relationships() {
+        let mut db = GraphDb::new();
+        let (john, acme, london) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+        let relationship = |source_id, relationship_type: &str, target_id| Fact::RelationshipAdded {
+            source_id,
+            target_id,
+            relationship_type: relationship_type.to_string(),
+            timestamp,
+            valid_from: 2020,
+            valid_to: None,
+        };
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp, properties: props(&[("name", "John")]) },
+                Fact::EntityCreated { entity_id: acme, timestamp, properties: props(&[("name", "Acme")]) },
+                Fact::EntityCreated { entity_id: london, timestamp, properties: props(&[("name", "London")]) },
+                relationship(john, "WorksAt", acme),
+                relationship(acme, "LocatedAt", london),
+            ],
+        });
+
+        let query = TimelineQuery { relationship_types: Some(vec!["WorksAt".to_string()]), ..Default::default() };
+        let result = generate_timeline(&db, &query);
+
+        assert_eq!(result.facts.len(), 1);
+        assert!(matches!(&result.facts[0], Fact::RelationshipAdded { relationship_type, .. } if relationship_type == "WorksAt"));
+        assert!(!result.facts.iter().any(|f| matches!(f, Fact::RelationshipAdded { relationship_type, .. } if relationship_type == "LocatedAt")));
+    }
+
+    #[test]
+    fn test_named_json_resolves_known_entities_and_falls_back_to_uuid() {
+        let mut db = GraphDb::new();
+        let (john, ghost) = (Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+
+        db.add_fact(FactStore {
+            facts: vec![Fact::EntityCreated { entity_id: john, timestamp, properties: props(&[("name", "John")]) }],
+        });
+        // A fact about an entity the graph has never seen
+        let result = TimelineResult {
+            facts: vec![
+                db.event_log[0].clone(),
+                Fact::RelationshipInvalidated { source_id: john, target_id: ghost, timestamp, relationship_type: None },
+            ],
+            entries: Vec::new(),
+        };
+
+        let parsed: Value = serde_json::from_str(&timeline_to_named_json(&result, &db, None)).unwrap();
+
+        assert_eq!(parsed[0]["entity"]["id"], john.to_string());
+        assert_eq!(parsed[0]["entity"]["name"], "John");
+        assert_eq!(parsed[1]["source"]["name"], "John");
+        assert_eq!(parsed[1]["target"]["id"], ghost.to_string());
+        assert_eq!(parsed[1]["target"]["name"], ghost.to_string());
+    }
+
+    #[test]
+    fn test_validity_aware_timeline_marks_relationship_start_and_end() {
+        let mut db = GraphDb::new();
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp, properties: props(&[("name", "John")]) },
+                Fact::EntityCreated { entity_id: acme, timestamp, properties: props(&[("name", "Acme")]) },
+                Fact::RelationshipAdded {
+                    source_id: john,
+                    target_id: acme,
+                    relationship_type: "WorksAt".to_string(),
+                    timestamp,
+                    valid_from: 2020,
+                    valid_to: Some(2022),
+                },
+            ],
+        });
+
+        let query = TimelineQuery { validity_aware: true, ..Default::default() };
+        let result = generate_timeline(&db, &query);
+
+        // The relationship's lifespan predates the creations that were just recorded
+        let markers: Vec<TimelineMarker> = result.entries.iter().map(|e| e.marker).collect();
+        assert_eq!(
+            markers,
+            vec![TimelineMarker::ValidFrom, TimelineMarker::ValidUntil, TimelineMarker::Recorded, TimelineMarker::Recorded]
+        );
+        assert_eq!(result.entries[0].at, Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(result.entries[1].at, Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap());
+
+        // The default mode keeps relationships at their recording time, with no end marker
+        let plain = generate_timeline(&db, &TimelineQuery::default());
+        assert!(plain.entries.iter().all(|e| e.marker == TimelineMarker::Recorded));
+        assert_eq!(plain.entries.len(), 3);
+    }
 }
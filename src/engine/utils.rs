@@ -1,15 +1,18 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use crate::graph::fact::Fact;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
 
 /// Sorts a mutable list of facts chronologically in ascending order (oldest first)
 /// 
 /// This ensures that facts are processed or displayed in order they occurred,
 /// which is useful for timelines, replaying event logs, or building coherrent case narratives.
-pub fn sort_facts_by_time(facts: &mut Vec<Fact>) {
-    
-    // Sort based on each fact's timestamp
-    facts.sort_by_key(|f| f.timestamp());
+/// Each fact comes paired with its sequence number (see `GraphDb::sequenced_facts`), and facts
+/// sharing a timestamp are ordered by it, i.e. in the order they were recorded.
+pub fn sort_facts_by_time(facts: &mut [(u64, &Fact)]) {
+
+    // Sort on timestamp, then sequence number
+    facts.sort_by_key(|(seq, f)| (f.timestamp(), *seq));
 }
 
 /// Deduplicates a vector of facts, preserving the original order of first occurrence.
@@ -33,4 +36,141 @@ pub fn deduplicate_facts(facts: Vec<Fact>) -> Vec<Fact> {
     }
     
     result
-}
\ No newline at end of file
+}
+
+/// Collapses repeated facts that differ only in their timestamp (e.g. the same edge logged twice a second apart),
+/// keeping the first of them. Expects facts in chronological order.
+///
+/// Only back-to-back repeats about the same subject (an entity, or a source/target pair for relationship facts)
+/// collapse: an edge re-added after it was invalidated, or a property set back to an earlier value, is real history and stays.
+pub fn deduplicate_facts_by_content(facts: Vec<Fact>) -> Vec<Fact> {
+
+    // The latest fact seen for each subject, timestamp blanked out
+    let mut latest: HashMap<(Uuid, Uuid), Fact> = HashMap::new();
+
+    facts.into_iter()
+        .filter(|fact| {
+            let content = fact.with_timestamp(DateTime::UNIX_EPOCH);
+            latest.insert(subject(fact), content.clone()) != Some(content)
+        })
+        .collect()
+}
+
+/// What a fact is about, for `deduplicate_facts_by_content`: the entity (paired with the nil UUID),
+/// the source and target of a relationship, or the two entities of a merge.
+fn subject(fact: &Fact) -> (Uuid, Uuid) {
+    match fact {
+        Fact::EntityCreated { entity_id, .. }
+        | Fact::EntityUpdated { entity_id, .. }
+        | Fact::EntityDeleted { entity_id, .. } => (*entity_id, Uuid::nil()),
+        Fact::RelationshipAdded { source_id, target_id, .. }
+        | Fact::RelationshipInvalidated { source_id, target_id, .. } => (*source_id, *target_id),
+        Fact::EntityMerged { kept_id, removed_id, .. } => (*kept_id, *removed_id),
+    }
+}
+
+/// Describes how long ago a timestamp was, relative to now (e.g. "3 days ago").
+pub fn humanize(ts: DateTime<Utc>) -> String {
+    humanize_at(ts, Utc::now())
+}
+
+/// Same as [`humanize`], but measured against an explicit `now` so the result is deterministic.
+pub fn humanize_at(ts: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let elapsed = now.signed_duration_since(ts);
+    let seconds = elapsed.num_seconds().abs();
+
+    // Pick the largest unit that fits
+    let (amount, unit) = if seconds < 60 {
+        return "just now".to_string();
+    } else if seconds < 3_600 {
+        (seconds / 60, "minute")
+    } else if seconds < 86_400 {
+        (seconds / 3_600, "hour")
+    } else if seconds < 30 * 86_400 {
+        (seconds / 86_400, "day")
+    } else if seconds < 365 * 86_400 {
+        (seconds / (30 * 86_400), "month")
+    } else {
+        (seconds / (365 * 86_400), "year")
+    };
+
+    let plural = if amount == 1 { "" } else { "s" };
+    if elapsed.num_seconds() >= 0 {
+        format!("{} {}{} ago", amount, unit, plural)
+    } else {
+        format!("in {} {}{}", amount, unit, plural)
+    }
+}
+
+/// Formats a timestamp for display, either absolute (`%Y-%m-%d %H:%M:%S`) or relative to now.
+/// Absolute times are shown in the machine's local timezone; only display converts out of UTC.
+pub fn format_timestamp(ts: &DateTime<Utc>, relative: bool) -> String {
+    if relative {
+        humanize(*ts)
+    } else {
+        ts.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date as midnight UTC at the start of that day.
+/// Returns `None` for anything else, including impossible dates like `2024-02-30`.
+pub fn parse_date(input: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)
+        .map(|midnight| midnight.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::graph::fact::FactStore;
+    use crate::graph::GraphDb;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_sort_breaks_timestamp_ties_by_sequence_number() {
+        let timestamp = Utc::now();
+        let created: Vec<Fact> = ["John", "Acme", "London"].iter()
+            .map(|name| Fact::EntityCreated {
+                entity_id: Uuid::new_v4(),
+                timestamp,
+                properties: BTreeMap::from([("name".to_string(), name.to_string())]),
+            })
+            .collect();
+        let mut db = GraphDb::new();
+        for fact in &created {
+            db.add_fact(FactStore { facts: vec![fact.clone()] });
+        }
+
+        let mut facts: Vec<(u64, &Fact)> = db.sequenced_facts().collect();
+        facts.reverse();
+        sort_facts_by_time(&mut facts);
+
+        assert!(facts.into_iter().map(|(_, fact)| fact).eq(&created));
+    }
+
+    #[test]
+    fn test_humanize_known_offsets() {
+        let now = Utc::now();
+
+        assert_eq!(humanize_at(now - Duration::seconds(10), now), "just now");
+        assert_eq!(humanize_at(now - Duration::minutes(1), now), "1 minute ago");
+        assert_eq!(humanize_at(now - Duration::hours(5), now), "5 hours ago");
+        assert_eq!(humanize_at(now - Duration::days(3), now), "3 days ago");
+        assert_eq!(humanize_at(now - Duration::days(65), now), "2 months ago");
+        assert_eq!(humanize_at(now - Duration::days(800), now), "2 years ago");
+        assert_eq!(humanize_at(now + Duration::days(2), now), "in 2 days");
+    }
+
+    #[test]
+    fn test_parse_date_accepts_only_calendar_dates() {
+        use chrono::TimeZone;
+
+        assert_eq!(parse_date("2024-03-15"), Some(Utc.with_ymd_and_hms(2024, 3, 15, 0, 0, 0).unwrap()));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("15/03/2024"), None);
+        assert_eq!(parse_date("John"), None);
+    }
+}
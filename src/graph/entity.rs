@@ -1,3 +1,4 @@
+use std::fmt;
 use std::str::FromStr;
 use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
@@ -14,21 +15,27 @@ pub enum EntityType {
     Place,
     Action,
     Event,
+    Domain,
+    IpAddress,
+    Username,
+    Organization,
+    Document,
+    Vehicle,
     Unknown,
+    Custom(String), // Any other type label, kept as given (e.g. "BankAccount")
 }
 
 impl EntityType {
     pub fn from_properties(props: &BTreeMap<String, String>) -> Self {
-        match props.get("type").map(String::as_str) {
-            Some("Person") => EntityType::Person,
-            Some("PhoneNumber") => EntityType::PhoneNumber,
-            Some("Email") => EntityType::Email,
-            Some("Company") => EntityType::Company,
-            Some("Product") => EntityType::Product,
-            Some("Place") => EntityType::Place,
-            Some("Action") => EntityType::Action,
-            Some("Event") => EntityType::Event,
-            _ => EntityType::Unknown,
+        props.get("type").map_or(EntityType::Unknown, |t| t.parse().unwrap_or(EntityType::Unknown))
+    }
+
+    /// Like `from_properties`, but first maps legacy `type` values through `aliases`
+    /// (e.g. "Org" -> "Company") so old logs keep their meaning.
+    pub fn from_properties_with_aliases(props: &BTreeMap<String, String>, aliases: &HashMap<String, String>) -> Self {
+        match props.get("type").and_then(|t| aliases.get(t)) {
+            Some(current) => current.parse().unwrap_or(EntityType::Unknown),
+            None => EntityType::from_properties(props),
         }
     }
 }
@@ -45,16 +52,26 @@ impl ToString for EntityType{
             EntityType::Place => "Place".to_string(),
             EntityType::Action => "Action".to_string(),
             EntityType::Event => "Event".to_string(),
+            EntityType::Domain => "Domain".to_string(),
+            EntityType::IpAddress => "IpAddress".to_string(),
+            EntityType::Username => "Username".to_string(),
+            EntityType::Organization => "Organization".to_string(),
+            EntityType::Document => "Document".to_string(),
+            EntityType::Vehicle => "Vehicle".to_string(),
             EntityType::Unknown => "Unknown".to_string(),
+            EntityType::Custom(label) => label.clone(),
         }
     }
 }
 
 
+// Known labels map to their variant; anything else becomes `Custom`.
+// Only a blank label is rejected.
 impl FromStr for EntityType{
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
         match s {
             "Person" => Ok(EntityType::Person),
             "PhoneNumber" => Ok(EntityType::PhoneNumber),
@@ -64,15 +81,145 @@ impl FromStr for EntityType{
             "Place" => Ok(EntityType::Place),
             "Action" => Ok(EntityType::Action),
             "Event" => Ok(EntityType::Event),
-            _ => Err(()),
+            "Domain" => Ok(EntityType::Domain),
+            "IpAddress" => Ok(EntityType::IpAddress),
+            "Username" => Ok(EntityType::Username),
+            "Organization" => Ok(EntityType::Organization),
+            "Document" => Ok(EntityType::Document),
+            "Vehicle" => Ok(EntityType::Vehicle),
+            "Unknown" => Ok(EntityType::Unknown),
+            "" => Err(()),
+            _ => Ok(EntityType::Custom(s.to_string())),
         }
     }
 }
 
+// Property holding an entity's alternative names, comma-separated; mirrored into `Entity::aliases`.
+pub const ALIASES_KEY: &str = "aliases";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entity {
     pub id: Uuid,
     pub name: String,
     pub entity_type: EntityType,
-    pub properties: BTreeMap<String, String>
+    pub properties: BTreeMap<String, String>,
+    #[serde(default)]
+    pub aliases: Vec<String>, // Other names the entity is known by, matched like `name` by lookups and search
+}
+
+impl Entity {
+    // Splits an `aliases` property value into the individual names, dropping blanks.
+    pub fn parse_aliases(value: &str) -> Vec<String> {
+        value.split(',').map(str::trim).filter(|a| !a.is_empty()).map(String::from).collect()
+    }
+
+    // Whether `name` is the entity's name or one of its aliases, ignoring ASCII case.
+    pub fn is_called(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    // Label to show for the entity: its `label_key` property when one is given and present, otherwise its name.
+    pub fn display_name(&self, label_key: Option<&str>) -> String {
+        label_key
+            .and_then(|key| self.properties.get(key))
+            .unwrap_or(&self.name)
+            .clone()
+    }
+}
+
+// Why an entity's name doesn't fit its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    InvalidEmail(String),       // Needs a non-empty local part and domain around a single `@`
+    InvalidPhoneNumber(String), // Only digits, `+` and spaces, with at least one digit
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidEmail(value) => {
+                write!(f, "'{}' is not an email address (expected local@domain)", value)
+            }
+            ValidationError::InvalidPhoneNumber(value) => {
+                write!(f, "'{}' is not a phone number (only digits, '+' and spaces are allowed)", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Entity {
+    // Checks that the name makes sense for the entity type;
+    //      Email: one `@` with a non-empty part on each side.
+    //      PhoneNumber: digits, `+` and spaces only.
+    // Other types accept any name.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self.entity_type {
+            EntityType::Email => match self.name.split_once('@') {
+                Some((local, domain)) if !local.is_empty() && !domain.is_empty() && !domain.contains('@') => Ok(()),
+                _ => Err(ValidationError::InvalidEmail(self.name.clone())),
+            },
+            EntityType::PhoneNumber => {
+                let allowed = self.name.chars().all(|c| c.is_ascii_digit() || c == '+' || c == ' ');
+                if allowed && self.name.chars().any(|c| c.is_ascii_digit()) {
+                    Ok(())
+                } else {
+                    Err(ValidationError::InvalidPhoneNumber(self.name.clone()))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(name: &str, entity_type: EntityType) -> Entity {
+        Entity { id: Uuid::new_v4(), name: name.to_string(), entity_type, properties: BTreeMap::new(), aliases: Vec::new() }
+    }
+
+    #[test]
+    fn test_validate_accepts_well_formed_email() {
+        assert_eq!(entity("john@example.com", EntityType::Email).validate(), Ok(()));
+        assert_eq!(entity("+44 20 7946 0000", EntityType::PhoneNumber).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_email() {
+        for name in ["foo", "@example.com", "john@", "a@b@c"] {
+            assert!(matches!(entity(name, EntityType::Email).validate(), Err(ValidationError::InvalidEmail(_))));
+        }
+        assert!(entity("555-0101", EntityType::PhoneNumber).validate().is_err());
+        // Types without a format accept anything
+        assert_eq!(entity("foo", EntityType::Person).validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_osint_types_round_trip_through_labels() {
+        for (label, etype) in [
+            ("Domain", EntityType::Domain),
+            ("IpAddress", EntityType::IpAddress),
+            ("Username", EntityType::Username),
+            ("Organization", EntityType::Organization),
+            ("Document", EntityType::Document),
+            ("Vehicle", EntityType::Vehicle),
+        ] {
+            assert_eq!(EntityType::from_str(label), Ok(etype.clone()));
+            assert_eq!(etype.to_string(), label);
+        }
+    }
+
+    #[test]
+    fn test_type_labels_are_trimmed_before_matching() {
+        assert_eq!(EntityType::from_str(" Person "), Ok(EntityType::Person));
+        assert_eq!(EntityType::from_str(" BankAccount "), Ok(EntityType::Custom("BankAccount".to_string())));
+        assert_eq!(EntityType::from_str("  "), Err(()));
+
+        let props = BTreeMap::from([("type".to_string(), "Company ".to_string())]);
+        assert_eq!(EntityType::from_properties(&props), EntityType::Company);
+        assert_eq!(EntityType::from_properties(&BTreeMap::new()), EntityType::Unknown);
+    }
 }
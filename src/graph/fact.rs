@@ -1,37 +1,50 @@
-use crate::graph::Entity;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 use chrono::prelude::*;
 
+/// A single recorded change to the graph.
+///
+/// Timestamps are recorded in UTC, and every time comparison (case windows, timelines,
+/// activity) happens in UTC; only display converts to the local timezone. Logs written
+/// with a local offset still load, since the offset is converted on deserialization.
 #[derive(Debug, Eq, Clone, Serialize, Deserialize, PartialEq, Hash)]
 pub enum Fact {
     EntityCreated {
         entity_id: Uuid,
-        timestamp: DateTime<Local>,
+        timestamp: DateTime<Utc>,
         properties: BTreeMap<String, String>,
     },
     EntityUpdated {
         entity_id: Uuid,
-        timestamp: DateTime<Local>,
+        timestamp: DateTime<Utc>,
         updated_properties: BTreeMap<String, String>,
     },
     EntityDeleted {
         entity_id: Uuid,
-        timestamp: DateTime<Local>,
+        timestamp: DateTime<Utc>,
     },
     RelationshipAdded {
         source_id: Uuid,
         target_id: Uuid,
         relationship_type: String,
-        timestamp: DateTime<Local>,
+        timestamp: DateTime<Utc>,
         valid_from: i64,
         valid_to: Option<i64>,
     },
     RelationshipInvalidated {
         source_id: Uuid,
         target_id: Uuid,
-        timestamp: DateTime<Local>,
+        timestamp: DateTime<Utc>,
+        // Only edges of this type are removed; None removes every edge from source to target.
+        // Left out of the JSON when None, so older logs read back and hash exactly as before.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        relationship_type: Option<String>,
+    },
+    EntityMerged {
+        kept_id: Uuid,
+        removed_id: Uuid,
+        timestamp: DateTime<Utc>,
     },
 }
 
@@ -42,9 +55,24 @@ impl Fact {
             | Fact::EntityUpdated { timestamp, .. }
             | Fact::EntityDeleted { timestamp, .. }
             | Fact::RelationshipAdded { timestamp, .. }
-            | Fact::RelationshipInvalidated { timestamp, .. } => timestamp.with_timezone(&Utc),
+            | Fact::RelationshipInvalidated { timestamp, .. }
+            | Fact::EntityMerged { timestamp, .. } => *timestamp,
         }
     }
+
+    /// A copy of this fact stamped with `at` instead, e.g. to compare facts by content alone.
+    pub fn with_timestamp(&self, at: DateTime<Utc>) -> Fact {
+        let mut fact = self.clone();
+        match &mut fact {
+            Fact::EntityCreated { timestamp, .. }
+            | Fact::EntityUpdated { timestamp, .. }
+            | Fact::EntityDeleted { timestamp, .. }
+            | Fact::RelationshipAdded { timestamp, .. }
+            | Fact::RelationshipInvalidated { timestamp, .. }
+            | Fact::EntityMerged { timestamp, .. } => *timestamp = at,
+        }
+        fact
+    }
 }
 
 
@@ -60,13 +88,16 @@ impl Fact {
             | Fact::RelationshipInvalidated { source_id, target_id, .. } => {
                 entity_ids.contains(source_id) || entity_ids.contains(target_id)
             }
+            Fact::EntityMerged { kept_id, removed_id, .. } => {
+                entity_ids.contains(kept_id) || entity_ids.contains(removed_id)
+            }
         }
     }
 }
 
+/// A batch of facts handed to `GraphDb::add_fact`, applied in order.
+/// Entity and relationship facts share the one list, so a batch can create entities and link them.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FactStore {
     pub facts: Vec<Fact>,
-    //pub entities: Vec<Entity>,
-    //pub relationships: Vec<Fact>,
 }
@@ -1,19 +1,110 @@
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::fs::File;
 use std::fs;
+use std::path::{Path, PathBuf};
 use petgraph::prelude::EdgeRef;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use serde_json;
 
 use crate::graph::fact::{Fact, FactStore};
-use crate::graph::{Entity, EntityType, Relationship};
+use crate::graph::{Entity, EntityType, Relationship, RelationshipType, ALIASES_KEY};
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+// How far past the current time a fact may be stamped before reject_future_facts treats it as a data-entry error(absorbs small clock skew).
+const FUTURE_FACT_TOLERANCE_SECS: i64 = 300;
+
+/// Counts of each kind of fact in the event log, used to gauge how churny a dataset is.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct LifecycleSummary {
+    pub entities_created: usize,
+    pub entities_updated: usize,
+    pub entities_deleted: usize,
+    pub relationships_added: usize,
+    pub relationships_invalidated: usize,
+    pub entities_merged: usize,
+}
+
+/// Reasons a direct graph mutation was refused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError {
+    SourceMissing(Uuid), // The relationship's source entity isn't in the graph
+    TargetMissing(Uuid), // The relationship's target entity isn't in the graph
+    DuplicateEntity(Uuid), // An entity with this UUID already exists
+    EntityMissing(Uuid), // The entity to operate on isn't in the graph
+    SelfMerge(Uuid), // An entity can't be merged into itself
+    FutureFact(DateTime<Utc>), // The fact is dated too far past now(only with reject_future_facts)
+    BlankRelationshipType(Uuid, Uuid), // The relationship between these entities has no type
+}
+
+impl std::fmt::Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::SourceMissing(id) => write!(f, "relationship source {} is not in the graph", id),
+            GraphError::TargetMissing(id) => write!(f, "relationship target {} is not in the graph", id),
+            GraphError::DuplicateEntity(id) => write!(f, "an entity with id {} already exists", id),
+            GraphError::EntityMissing(id) => write!(f, "entity {} is not in the graph", id),
+            GraphError::SelfMerge(id) => write!(f, "entity {} can't be merged into itself", id),
+            GraphError::FutureFact(at) => write!(f, "fact dated in the future ({})", at),
+            GraphError::BlankRelationshipType(source, target) => write!(f, "relationship {} -> {} has a blank type", source, target),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// Header written at the top of a save file, describing the log that follows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogMetadata {
+    pub fact_count: usize,
+    pub log_hash: String, // GraphDb::log_hash of the saved facts, checked again on load to detect edits
+    #[serde(default)]
+    pub next_seq: u64, // Sequence number the next recorded fact gets(0 in saves that predate sequence numbers)
+    #[serde(default)]
+    pub unchained: bool, // The saved hash chain was generated on load rather than recorded, so verify_integrity can't vouch for it
+}
+
+/// On-disk save format: metadata header plus the event log.
+/// Older saves are a bare array of facts and still load, just without verification.
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    metadata: LogMetadata,
+    facts: Vec<Fact>,
+    #[serde(default)]
+    seqs: Vec<u64>, // Sequence number of each fact; absent in older saves, which are numbered in order on load
+    #[serde(default)]
+    hashes: Vec<String>, // Hash chain over the facts; absent in older saves, which start a fresh chain on load
+    #[serde(default)]
+    batches: Vec<usize>, // Where each add_fact batch starts in `facts`; absent in older saves, whose facts undo one at a time
+}
 
 pub struct GraphDb {
     pub graph: StableDiGraph<Entity, Relationship>, // The actual petgraph graph, storing entities as nodes and relationships as edges.
     pub uuid_index_map: HashMap<Uuid, NodeIndex>, // A lookup table that maps each Entity's UUID to its corresponding node in the graph(without this we'd need to search the whole graph to find a node).
-    pub(crate) event_log: Vec<Fact>, // Stores all facts
+    pub(crate) event_log: Vec<Fact>, // Stores all facts, in the order they were recorded
+    pub(crate) fact_seqs: Vec<u64>, // Sequence number of each event_log fact, index for index(assigned when recorded, never reused).
+    pub(crate) next_seq: u64, // Sequence number the next recorded fact gets.
+    pub(crate) fact_hashes: Vec<String>, // Hash chain over event_log, index for index: sha256(fact || previous hash), see verify_integrity.
+    pub(crate) unchained: bool, // Set when the log was loaded without a complete hash chain, so fact_hashes were generated rather than stored.
+    pub(crate) batch_starts: Vec<usize>, // event_log length before each add_fact call that recorded something, so undo_last can take back a whole batch.
+    pub property_index: HashMap<(String, String), Vec<Uuid>>, // Inverted index from (property key, value) to the UUIDs of entities holding that value(avoids scanning every node).
+    pub name_index: HashMap<String, Vec<Uuid>>, // Lower-cased entity name to the UUIDs of entities with that name, for case-insensitive lookups.
+    pub type_aliases: HashMap<String, String>, // Legacy entity `type` values mapped to current EntityType names(e.g. "Org" -> "Company"), applied on create and on load.
+    pub last_activity: HashMap<Uuid, DateTime<Utc>>, // Latest fact timestamp per entity(relationship facts count for both endpoints).
+    pub dedup_on_ingest: bool, // When set, add_fact skips any fact identical to one already in the event log(keeps repeated imports from bloating the log).
+    pub reject_future_facts: bool, // When set, add_fact rejects(and logs) facts dated after now plus a small tolerance.
+    pub last_flushed: usize, // How many event_log facts persist_facts_append has already written out.
+    pub load_warnings: Vec<String>, // Problems noticed while loading from disk(e.g. an integrity hash mismatch); empty for graphs built in memory.
+}
+
+impl Default for GraphDb {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GraphDb {
@@ -23,6 +114,19 @@ impl GraphDb {
             graph: StableDiGraph::new(),
             uuid_index_map: HashMap::new(),
             event_log: Vec::new(),
+            fact_seqs: Vec::new(),
+            next_seq: 0,
+            fact_hashes: Vec::new(),
+            unchained: false,
+            batch_starts: Vec::new(),
+            property_index: HashMap::new(),
+            name_index: HashMap::new(),
+            type_aliases: HashMap::new(),
+            last_activity: HashMap::new(),
+            dedup_on_ingest: false,
+            reject_future_facts: false,
+            last_flushed: 0,
+            load_warnings: Vec::new(),
         }
     }
     
@@ -30,29 +134,152 @@ impl GraphDb {
     // If not adds the Entity to the graph using add_node().
     // Gets back the NodeIndex and store it in the uuid_index_map.
     // We use .clone() while adding the node because Petgraph owns its data internally, and we may want to keep using the original Entity outside the graph.
-    pub fn add_entity(&mut self, entity: Entity) {
+    // Returns the new NodeIndex, or DuplicateEntity(leaving the existing entity untouched) if the UUID is taken.
+    pub fn add_entity(&mut self, entity: Entity) -> Result<NodeIndex, GraphError> {
         if self.uuid_index_map.contains_key(&entity.id) {
-            // Prevent duplicates - log or silently ignore
-            return;
+            return Err(GraphError::DuplicateEntity(entity.id));
         }
         let node_index = self.graph.add_node(entity.clone());
         self.uuid_index_map.insert(entity.id, node_index);
+        for name in std::iter::once(&entity.name).chain(&entity.aliases) {
+            self.index_name(entity.id, name);
+        }
+        for (key, value) in &entity.properties {
+            self.index_property(entity.id, key, value);
+        }
+        Ok(node_index)
+    }
+
+    // Records that the entity answers to `name`(its name or an alias) in the name index.
+    fn index_name(&mut self, entity_id: Uuid, name: &str) {
+        let ids = self.name_index.entry(name.to_lowercase()).or_default();
+        if !ids.contains(&entity_id) {
+            ids.push(entity_id);
+        }
+    }
+
+    // Removes the entity from the name index entry for `name`, dropping the entry once it's empty.
+    fn unindex_name(&mut self, entity_id: Uuid, name: &str) {
+        let name_key = name.to_lowercase();
+        if let Some(ids) = self.name_index.get_mut(&name_key) {
+            ids.retain(|id| *id != entity_id);
+            if ids.is_empty() {
+                self.name_index.remove(&name_key);
+            }
+        }
+    }
+
+    // Records that the entity holds `key = value` in the property index.
+    fn index_property(&mut self, entity_id: Uuid, key: &str, value: &str) {
+        let ids = self.property_index.entry((key.to_string(), value.to_string())).or_default();
+        if !ids.contains(&entity_id) {
+            ids.push(entity_id);
+        }
+    }
+
+    // Removes the entity from the `key = value` bucket, dropping the bucket once it's empty.
+    fn unindex_property(&mut self, entity_id: Uuid, key: &str, value: &str) {
+        let index_key = (key.to_string(), value.to_string());
+        if let Some(ids) = self.property_index.get_mut(&index_key) {
+            ids.retain(|id| *id != entity_id);
+            if ids.is_empty() {
+                self.property_index.remove(&index_key);
+            }
+        }
+    }
+
+    // When the entity last appeared in any fact, if ever.
+    pub fn last_activity(&self, uuid: &Uuid) -> Option<DateTime<Utc>> {
+        self.last_activity.get(uuid).copied()
+    }
+
+    // Returns every entity whose name or alias matches `name` ignoring case, in insertion order.
+    pub fn entities_named(&self, name: &str) -> Vec<&Entity> {
+        self.name_index
+            .get(&name.to_lowercase())
+            .map(|ids| ids.iter().filter_map(|id| self.get_entity(id)).collect())
+            .unwrap_or_default()
+    }
+
+    // Looks up a single entity by name, ignoring ASCII case("john" finds "John").
+    // When several entities share the name, the one created first is returned, so repeated lookups always agree.
+    pub fn find_by_name_ci(&self, name: &str) -> Option<&Entity> {
+        self.find_all_by_name(name).into_iter().next()
+    }
+
+    // Near matches for a possibly mistyped name;
+    //      1. Levenshtein distance between `name` and every entity name, ignoring case.
+    //      2. Keep those within `max_distance`, closest first(ties by name).
+    pub fn fuzzy_find(&self, name: &str, max_distance: usize) -> Vec<(&Entity, usize)> {
+        let wanted = name.to_lowercase();
+        let mut matches: Vec<(&Entity, usize)> = self.entities()
+            .map(|e| (e, levenshtein(&wanted, &e.name.to_lowercase())))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .collect();
+        matches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.name.cmp(&b.0.name)));
+        matches
+    }
+
+    // Every entity whose name or alias matches `name` ignoring ASCII case, oldest first.
+    // More than one result means the name alone can't identify an entity.
+    pub fn find_all_by_name(&self, name: &str) -> Vec<&Entity> {
+        self.entities_named(name).into_iter().filter(|e| e.is_called(name)).collect()
+    }
+
+    // Returns every entity whose property `key` currently equals `value`, using the property index instead of a full scan.
+    pub fn entities_with_property(&self, key: &str, value: &str) -> Vec<&Entity> {
+        self.property_index
+            .get(&(key.to_string(), value.to_string()))
+            .map(|ids| ids.iter().filter_map(|id| self.get_entity(id)).collect())
+            .unwrap_or_default()
     }
 
     // Looks up the source and target UUIDs in the uuid_index_map.
     // If both are found;
     //      1. Adds a directed edge from source to target.
     //      2. Associates it with the given Relationship.
-    // If either isn't found, it does nothing(add logging or error returns later).
-    pub fn add_relationship(&mut self, relationship: Relationship) {
-        let source_idx = self.uuid_index_map.get(&relationship.source_id);
-        let target_idx = self.uuid_index_map.get(&relationship.target_id);
+    // If either isn't found, nothing is added and the missing endpoint is reported(source first).
+    pub fn add_relationship(&mut self, relationship: Relationship) -> Result<(), GraphError> {
+        let Some(&source) = self.uuid_index_map.get(&relationship.source_id) else {
+            return Err(GraphError::SourceMissing(relationship.source_id));
+        };
+        let Some(&target) = self.uuid_index_map.get(&relationship.target_id) else {
+            return Err(GraphError::TargetMissing(relationship.target_id));
+        };
 
-        if let (Some(&source), Some(&target)) = (source_idx, target_idx) {
-            self.graph.add_edge(source, target, relationship);
-        } else {
-            // Optionally log: one or both entities not found
+        self.graph.add_edge(source, target, relationship);
+        Ok(())
+    }
+
+    // "Ensure this relationship exists" semantics for repeated imports;
+    //      1. If an edge of `rel_type` already connects `from` to `to`, do nothing.
+    //      2. Otherwise record a RelationshipAdded fact so the edge is created and logged.
+    // Returns whether a new edge was created, or why the fact was refused(a missing endpoint, a blank type).
+    pub fn ensure_relationship(&mut self, from: Uuid, to: Uuid, rel_type: &str, valid_from: i64, valid_to: Option<i64>) -> Result<bool, GraphError> {
+        let exists = match (self.uuid_index_map.get(&from), self.uuid_index_map.get(&to)) {
+            (Some(&source), Some(&target)) => self
+                .graph
+                .edges_connecting(source, target)
+                .any(|edge| edge.weight().relationship_type.to_string() == rel_type),
+            _ => false,
+        };
+        if exists {
+            return Ok(false);
         }
+
+        let logged = self.event_log.len();
+        self.add_facts_atomic(FactStore {
+            facts: vec![Fact::RelationshipAdded {
+                source_id: from,
+                target_id: to,
+                relationship_type: rel_type.to_string(),
+                timestamp: Utc::now(),
+                valid_from,
+                valid_to,
+            }],
+        })?;
+        // Nothing is recorded when dedup_on_ingest finds the fact already logged
+        Ok(self.event_log.len() > logged)
     }
 
     // Retrieves the actual Entity from the graph using its UUID;
@@ -105,21 +332,314 @@ impl GraphDb {
         neighbors
     }
 
-    pub fn add_fact(&mut self, fact_store: FactStore) {
+    // Counts the edges pointing into and out of the given node, returned as (incoming, outgoing).
+    // Unknown UUIDs report (0, 0).
+    pub fn io_balance(&self, uuid: &Uuid) -> (usize, usize) {
+        self.entity_degree(uuid).unwrap_or((0, 0))
+    }
+
+    // (in_degree, out_degree) of the entity, counting parallel edges separately; None for unknown UUIDs.
+    pub fn entity_degree(&self, uuid: &Uuid) -> Option<(usize, usize)> {
+        let &node_idx = self.uuid_index_map.get(uuid)?;
+        Some((
+            self.graph.edges_directed(node_idx, petgraph::Direction::Incoming).count(),
+            self.graph.edges_directed(node_idx, petgraph::Direction::Outgoing).count(),
+        ))
+    }
+
+    // The `n` hubs of the graph: entities ranked by total degree(in + out), highest first.
+    // Ties are broken by UUID so the ranking is stable.
+    pub fn most_connected(&self, n: usize) -> Vec<(Uuid, usize)> {
+        let mut ranked: Vec<(Uuid, usize)> = self.uuid_index_map
+            .keys()
+            .filter_map(|id| self.entity_degree(id).map(|(incoming, outgoing)| (*id, incoming + outgoing)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(n);
+        ranked
+    }
+
+    // "Who else works at the same company?";
+    //      1. Follow the entity's outgoing `rel_type` edges to their targets.
+    //      2. Collect every other entity with a `rel_type` edge into one of those targets.
+    // Each recommended entity appears once, in discovery order.
+    pub fn co_related(&self, entity: &Uuid, rel_type: &str) -> Vec<&Entity> {
+        let mut seen = std::collections::HashSet::new();
+        let mut related = Vec::new();
+
+        let Some(&node_idx) = self.uuid_index_map.get(entity) else {
+            return related;
+        };
+
+        let targets = self
+            .graph
+            .edges_directed(node_idx, petgraph::Direction::Outgoing)
+            .filter(|edge| edge.weight().relationship_type.to_string() == rel_type)
+            .map(|edge| edge.target());
+
+        for target in targets {
+            for edge in self.graph.edges_directed(target, petgraph::Direction::Incoming) {
+                if edge.source() == node_idx || edge.weight().relationship_type.to_string() != rel_type {
+                    continue;
+                }
+                if !seen.insert(edge.source()) {
+                    continue;
+                }
+                if let Some(other) = self.graph.node_weight(edge.source()) {
+                    related.push(other);
+                }
+            }
+        }
+
+        related
+    }
+
+    // Parallel edges, usually left behind by repeated imports;
+    // returns (source, target, relationship type, edge count) for every pair joined by more than one edge of the same type.
+    // Sorted by source, target, then type.
+    pub fn duplicate_edges(&self) -> Vec<(Uuid, Uuid, String, usize)> {
+        let mut counts: BTreeMap<(Uuid, Uuid, String), usize> = BTreeMap::new();
+        for rel in self.graph.edge_weights() {
+            *counts.entry((rel.source_id, rel.target_id, rel.relationship_type.to_string())).or_insert(0) += 1;
+        }
+
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|((source, target, rel_type), count)| (source, target, rel_type, count))
+            .collect()
+    }
+
+    // Outgoing neighbours reached over any of `rel_types`, treating them as one logical link
+    // (e.g. WorksAt and ConsultsFor). Each neighbour appears once.
+    pub fn neighbours_via_any(&self, uuid: &Uuid, rel_types: &[String]) -> Vec<&Entity> {
+        let mut seen = std::collections::HashSet::new();
+        let mut neighbours = Vec::new();
+
+        let Some(&node_idx) = self.uuid_index_map.get(uuid) else {
+            return neighbours;
+        };
+
+        for edge in self.graph.edges_directed(node_idx, petgraph::Direction::Outgoing) {
+            if !rel_types.contains(&edge.weight().relationship_type.to_string()) {
+                continue;
+            }
+            if !seen.insert(edge.target()) {
+                continue;
+            }
+            if let Some(entity) = self.graph.node_weight(edge.target()) {
+                neighbours.push(entity);
+            }
+        }
+
+        neighbours
+    }
+
+    // Second-degree contacts: entities exactly two hops away, ignoring edge direction;
+    //      1. Collect the seed's direct neighbours(incoming and outgoing).
+    //      2. Collect their neighbours, skipping the seed and anything already a direct neighbour.
+    // Each entity appears once.
+    pub fn second_degree(&self, uuid: &Uuid) -> Vec<&Entity> {
+        let mut result = Vec::new();
+
+        let Some(&node_idx) = self.uuid_index_map.get(uuid) else {
+            return result;
+        };
+
+        let direct: std::collections::HashSet<NodeIndex> = self.graph.neighbors_undirected(node_idx).collect();
+        let mut seen = std::collections::HashSet::new();
+
+        for &neighbour in &direct {
+            for candidate in self.graph.neighbors_undirected(neighbour) {
+                if candidate == node_idx || direct.contains(&candidate) || !seen.insert(candidate) {
+                    continue;
+                }
+                if let Some(entity) = self.graph.node_weight(candidate) {
+                    result.push(entity);
+                }
+            }
+        }
+
+        result
+    }
+
+    // Ranks other entities by how alike they are to `uuid`, for spotting duplicates and close associates;
+    //      1. Jaccard similarity of the two undirected neighbour sets.
+    //      2. Jaccard similarity of their (property key, value) pairs.
+    // The score is the mean of the two(0.0 to 1.0). Entities scoring 0 are left out; the best `top_n` are returned, highest first.
+    pub fn similar_entities(&self, uuid: &Uuid, top_n: usize) -> Vec<(Uuid, f32)> {
+        use std::collections::HashSet;
+
+        fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f32 {
+            let union = a.union(b).count();
+            if union == 0 {
+                return 0.0;
+            }
+            a.intersection(b).count() as f32 / union as f32
+        }
+
+        let (Some(&node_idx), Some(entity)) = (self.uuid_index_map.get(uuid), self.get_entity(uuid)) else {
+            return Vec::new();
+        };
+        let neighbours_of = |idx: NodeIndex| -> HashSet<NodeIndex> { self.graph.neighbors_undirected(idx).collect() };
+        let properties_of = |e: &Entity| -> HashSet<(String, String)> { e.properties.clone().into_iter().collect() };
+
+        let seed_neighbours = neighbours_of(node_idx);
+        let seed_properties = properties_of(entity);
+
+        let mut scored: Vec<(Uuid, f32)> = self
+            .graph
+            .node_indices()
+            .filter(|&idx| idx != node_idx)
+            .filter_map(|idx| {
+                let other = self.graph.node_weight(idx)?;
+                let score = (jaccard(&seed_neighbours, &neighbours_of(idx)) + jaccard(&seed_properties, &properties_of(other))) / 2.0;
+                (score > 0.0).then_some((other.id, score))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(top_n);
+        scored
+    }
+
+    // The network "as of" a year: every relationship whose validity window contains `year`
+    // (valid_from <= year, and valid_to either open-ended or >= year).
+    pub fn relationships_valid_at(&self, year: i64) -> Vec<&Relationship> {
+        self.graph
+            .edge_weights()
+            .filter(|rel| rel.valid_from <= year && rel.valid_to.is_none_or(|to| to >= year))
+            .collect()
+    }
+
+    // Splits the graph into its disjoint clusters, ignoring edge direction;
+    //      1. Pick an unvisited node and BFS from it over incoming and outgoing edges.
+    //      2. Everything reached is one component; repeat until every node is visited.
+    // Components come largest first(ties keep discovery order); UUIDs within one are in BFS order.
+    pub fn connected_components(&self) -> Vec<Vec<Uuid>> {
+        let mut visited = std::collections::HashSet::new();
+        let mut components = Vec::new();
+
+        for start in self.graph.node_indices() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut component = Vec::new();
+            let mut queue = std::collections::VecDeque::from([start]);
+            while let Some(idx) = queue.pop_front() {
+                if let Some(entity) = self.graph.node_weight(idx) {
+                    component.push(entity.id);
+                }
+                for neighbour in self.graph.neighbors_undirected(idx) {
+                    if visited.insert(neighbour) {
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            components.push(component);
+        }
+
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        components
+    }
+
+    // Summarises the multi-edges from `from` to `to` without altering them;
+    // returns (relationship type, number of edges of that type), sorted by type name.
+    pub fn merged_relationship(&self, from: &Uuid, to: &Uuid) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+        if let (Some(&source), Some(&target)) = (self.uuid_index_map.get(from), self.uuid_index_map.get(to)) {
+            for edge in self.graph.edges_connecting(source, target) {
+                *counts.entry(edge.weight().relationship_type.to_string()).or_default() += 1;
+            }
+        }
+
+        counts.into_iter().collect()
+    }
+
+    // "How is A connected to B?";
+    //      1. Run an unweighted A* (every edge costs 1, so it behaves as a BFS) from `from`, following edge direction.
+    //      2. Map the node path back to entity UUIDs.
+    // Returns the UUIDs from `from` to `to` inclusive, or None if either is unknown or `to` can't be reached.
+    pub fn shortest_path(&self, from: Uuid, to: Uuid) -> Option<Vec<Uuid>> {
+        let start = *self.uuid_index_map.get(&from)?;
+        let goal = *self.uuid_index_map.get(&to)?;
+
+        let (_, nodes) = petgraph::algo::astar(&self.graph, start, |idx| idx == goal, |_| 1, |_| 0)?;
+        nodes.into_iter().map(|idx| self.graph.node_weight(idx).map(|e| e.id)).collect()
+    }
+
+    // Every simple route from `from` to `to` along edge direction, for link analysis;
+    //      1. Depth-first search, extending the current path one outgoing neighbour at a time.
+    //      2. Skip neighbours already on the current path, so cycles can't loop forever.
+    //      3. Stop extending once the path has `max_depth` edges.
+    // Each path lists UUIDs from `from` to `to` inclusive. Parallel edges don't produce duplicate paths.
+    pub fn all_paths(&self, from: Uuid, to: Uuid, max_depth: usize) -> Vec<Vec<Uuid>> {
+        let (Some(&start), Some(&goal)) = (self.uuid_index_map.get(&from), self.uuid_index_map.get(&to)) else {
+            return Vec::new();
+        };
+
+        fn walk(db: &GraphDb, path: &mut Vec<NodeIndex>, goal: NodeIndex, max_depth: usize, found: &mut Vec<Vec<Uuid>>) {
+            let current = *path.last().unwrap();
+            if current == goal {
+                found.push(path.iter().filter_map(|&idx| db.graph.node_weight(idx).map(|e| e.id)).collect());
+                return;
+            }
+            if path.len() > max_depth {
+                return;
+            }
+            let mut next: Vec<NodeIndex> = db.graph.neighbors(current).collect();
+            next.sort();
+            next.dedup();
+            for neighbour in next {
+                if !path.contains(&neighbour) {
+                    path.push(neighbour);
+                    walk(db, path, goal, max_depth, found);
+                    path.pop();
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        walk(self, &mut vec![start], goal, max_depth, &mut found);
+        found
+    }
+
+    // Records each fact in the event log and applies it to the graph.
+    // Returns what went wrong with the facts that didn't take effect: future-dated facts rejected under
+    // reject_future_facts(not logged), and facts that are logged but couldn't change the graph(a create
+    // reusing a UUID, an edge with a missing endpoint or a blank type). Facts skipped by dedup_on_ingest aren't errors.
+    pub fn add_fact(&mut self, fact_store: FactStore) -> Vec<GraphError> {
+        let mut errors = Vec::new();
+        let batch_start = self.event_log.len();
         for fact in fact_store.facts.clone() {
+            if self.dedup_on_ingest && self.event_log.contains(&fact) {
+                continue;
+            }
+            if self.reject_future_facts {
+                let latest_allowed = chrono::Utc::now() + chrono::Duration::seconds(FUTURE_FACT_TOLERANCE_SECS);
+                if fact.timestamp() > latest_allowed {
+                    errors.push(GraphError::FutureFact(fact.timestamp()));
+                    continue;
+                }
+            }
             match &fact {
                 Fact::EntityCreated {
                     entity_id,
                     timestamp: _,
                     properties,
                 } => {
+                    let entity_type = EntityType::from_properties_with_aliases(properties, &self.type_aliases);
                     let entity = Entity {
                         id: *entity_id,
                         name: properties.get("name").cloned().unwrap_or_default(),
-                        entity_type: EntityType::from_properties(properties),
+                        entity_type,
                         properties: properties.clone(),
+                        aliases: properties.get(ALIASES_KEY).map(|a| Entity::parse_aliases(a)).unwrap_or_default(),
                     };
-                    self.add_entity(entity);
+                    if let Err(e) = self.add_entity(entity) {
+                        errors.push(e);
+                    }
                 }
                 Fact::EntityUpdated {
                     entity_id,
@@ -127,10 +647,53 @@ impl GraphDb {
                     updated_properties,
                 } => {
                     if let Some(&node_idx) = self.uuid_index_map.get(entity_id) {
+                        let mut replaced = Vec::new();
+                        let mut old_aliases = Vec::new();
+                        let mut old_name = None;
                         if let Some(entity) = self.graph.node_weight_mut(node_idx) {
                             for (k, v) in updated_properties {
-                                entity.properties.insert(k.clone(), v.clone());
+                                if let Some(old) = entity.properties.insert(k.clone(), v.clone()) {
+                                    replaced.push((k.clone(), old));
+                                }
+                            }
+                            if let Some(aliases) = updated_properties.get(ALIASES_KEY) {
+                                old_aliases = std::mem::replace(&mut entity.aliases, Entity::parse_aliases(aliases));
+                            }
+                            // `name` and `type` are the entity's own fields as well as properties
+                            if let Some(name) = updated_properties.get("name") {
+                                old_name = Some(std::mem::replace(&mut entity.name, name.clone()));
                             }
+                            if updated_properties.contains_key("type") {
+                                entity.entity_type = EntityType::from_properties_with_aliases(&entity.properties, &self.type_aliases);
+                            }
+                        }
+                        // Re-index names when the aliases changed(the name itself stays indexed)
+                        if let Some(aliases) = updated_properties.get(ALIASES_KEY) {
+                            let name = self.graph.node_weight(node_idx).map(|e| e.name.clone()).unwrap_or_default();
+                            for alias in old_aliases.iter().filter(|a| !a.eq_ignore_ascii_case(&name)) {
+                                self.unindex_name(*entity_id, alias);
+                            }
+                            for alias in Entity::parse_aliases(aliases) {
+                                self.index_name(*entity_id, &alias);
+                            }
+                        }
+                        // A rename moves the name index entry, unless the old name lives on as an alias
+                        if let Some(old_name) = old_name {
+                            let still_alias = self.graph.node_weight(node_idx)
+                                .is_some_and(|e| e.aliases.iter().any(|a| a.eq_ignore_ascii_case(&old_name)));
+                            if !still_alias {
+                                self.unindex_name(*entity_id, &old_name);
+                            }
+                            if let Some(name) = updated_properties.get("name") {
+                                self.index_name(*entity_id, name);
+                            }
+                        }
+                        // Keep the property index in step with the new values
+                        for (k, old) in replaced {
+                            self.unindex_property(*entity_id, &k, &old);
+                        }
+                        for (k, v) in updated_properties {
+                            self.index_property(*entity_id, k, v);
                         }
                     }
                 }
@@ -138,10 +701,7 @@ impl GraphDb {
                     entity_id,
                     timestamp,
                 } => {
-                    if let Some(&node_idx) = self.uuid_index_map.get(entity_id) {
-                        self.graph.remove_node(node_idx);
-                        self.uuid_index_map.remove(entity_id);
-                    }
+                    self.remove_entity_node(entity_id);
                 }
                 Fact::RelationshipAdded {
                     source_id,
@@ -151,110 +711,945 @@ impl GraphDb {
                     valid_from,
                     valid_to,
                 } => {
-                    let relationship = Relationship {
-                        source_id: *source_id,
-                        target_id: *target_id,
-                        relationship_type: relationship_type.parse().unwrap(),
-                        valid_from: *valid_from,
-                        valid_to: *valid_to
-                    };
-                    self.add_relationship(relationship);
+                    // Unrecognised labels parse as Custom, so only a blank label is rejected
+                    match relationship_type.parse() {
+                        Ok(relationship_type) => {
+                            let relationship = Relationship {
+                                source_id: *source_id,
+                                target_id: *target_id,
+                                relationship_type,
+                                valid_from: *valid_from,
+                                valid_to: *valid_to
+                            };
+                            // The fact is still logged, but the dropped edge must not go unnoticed
+                            if let Err(e) = self.add_relationship(relationship) {
+                                errors.push(e);
+                            }
+                        }
+                        Err(_) => errors.push(GraphError::BlankRelationshipType(*source_id, *target_id)),
+                    }
                 }
                 Fact::RelationshipInvalidated {
                     source_id,
                     target_id,
-                    timestamp,
+                    relationship_type,
+                    ..
                 } => {
                     if let (Some(&src), Some(&tgt)) = (
                         self.uuid_index_map.get(source_id), 
                         self.uuid_index_map.get(target_id),
                     ) {
-                        let edges: Vec<_> = self.graph.edges_connecting(src, tgt).map(|e| e.id()).collect();
+                        let edges: Vec<_> = self.graph.edges_connecting(src, tgt)
+                            .filter(|e| relationship_type.as_ref().is_none_or(|t| e.weight().relationship_type.to_string() == *t))
+                            .map(|e| e.id())
+                            .collect();
                         for edge in edges {
                             self.graph.remove_edge(edge);
                         }
                     }
                 }
+                Fact::EntityMerged {
+                    kept_id,
+                    removed_id,
+                    ..
+                } => {
+                    self.apply_merge(*kept_id, *removed_id);
+                }
+            }
+
+            // Keep the last-activity index current for every entity the fact touches
+            let timestamp = fact.timestamp();
+            let involved = match &fact {
+                Fact::EntityCreated { entity_id, .. }
+                | Fact::EntityUpdated { entity_id, .. }
+                | Fact::EntityDeleted { entity_id, .. } => vec![*entity_id],
+                Fact::RelationshipAdded { source_id, target_id, .. }
+                | Fact::RelationshipInvalidated { source_id, target_id, .. } => vec![*source_id, *target_id],
+                Fact::EntityMerged { kept_id, removed_id, .. } => vec![*kept_id, *removed_id],
+            };
+            for entity_id in involved {
+                let latest = self.last_activity.entry(entity_id).or_insert(timestamp);
+                if timestamp > *latest {
+                    *latest = timestamp;
+                }
             }
-            // Persist every fact
+
+            // Persist every fact, stamped with the next sequence number and chained to the previous fact's hash
+            let previous_hash = self.fact_hashes.last().map(String::as_str).unwrap_or("");
+            self.fact_hashes.push(chain_hash(&fact, previous_hash));
             self.event_log.push(fact);
+            self.fact_seqs.push(self.next_seq);
+            self.next_seq += 1;
+        }
+        if self.event_log.len() > batch_start {
+            self.batch_starts.push(batch_start);
         }
+        errors
     }
 
-    pub fn persist_facts(&self, path: &str) -> std::io::Result<()> {
-        let serialized = serde_json::to_string_pretty(&self.event_log)?;
-        let mut file = File::create(path)?;
-        file.write_all(serialized.as_bytes())?;
+    // All-or-nothing alternative to add_fact;
+    //      1. Walk the batch in order against the entities that would exist at each step(the graph's, plus those
+    //         the batch itself creates, minus those it deletes or merges away), passing over facts dedup_on_ingest would skip.
+    //      2. Refuse the whole batch at the first fact that couldn't apply: a future-dated fact under reject_future_facts,
+    //         a relationship with a missing endpoint or a blank type, an update, delete or merge of a missing entity,
+    //         or a create reusing an existing UUID.
+    //      3. Otherwise record every fact through add_fact.
+    // On error neither the graph nor the event log is touched.
+    pub fn add_facts_atomic(&mut self, fact_store: FactStore) -> Result<(), GraphError> {
+        let mut existing: HashSet<Uuid> = self.uuid_index_map.keys().copied().collect();
+        let latest_allowed = Utc::now() + chrono::Duration::seconds(FUTURE_FACT_TOLERANCE_SECS);
+        let mut recorded: Vec<&Fact> = Vec::new();
+        for fact in &fact_store.facts {
+            if self.dedup_on_ingest && (self.event_log.contains(fact) || recorded.contains(&fact)) {
+                continue;
+            }
+            if self.reject_future_facts && fact.timestamp() > latest_allowed {
+                return Err(GraphError::FutureFact(fact.timestamp()));
+            }
+            recorded.push(fact);
+            match fact {
+                Fact::EntityCreated { entity_id, .. } => {
+                    if !existing.insert(*entity_id) {
+                        return Err(GraphError::DuplicateEntity(*entity_id));
+                    }
+                }
+                Fact::EntityUpdated { entity_id, .. } => {
+                    if !existing.contains(entity_id) {
+                        return Err(GraphError::EntityMissing(*entity_id));
+                    }
+                }
+                Fact::EntityDeleted { entity_id, .. } => {
+                    if !existing.remove(entity_id) {
+                        return Err(GraphError::EntityMissing(*entity_id));
+                    }
+                }
+                Fact::RelationshipAdded { source_id, target_id, relationship_type, .. }
+                    if relationship_type.parse::<RelationshipType>().is_err() => {
+                    return Err(GraphError::BlankRelationshipType(*source_id, *target_id));
+                }
+                Fact::RelationshipAdded { source_id, target_id, .. }
+                | Fact::RelationshipInvalidated { source_id, target_id, .. } => {
+                    if !existing.contains(source_id) {
+                        return Err(GraphError::SourceMissing(*source_id));
+                    }
+                    if !existing.contains(target_id) {
+                        return Err(GraphError::TargetMissing(*target_id));
+                    }
+                }
+                Fact::EntityMerged { kept_id, removed_id, .. } => {
+                    if kept_id == removed_id {
+                        return Err(GraphError::SelfMerge(*kept_id));
+                    }
+                    for id in [kept_id, removed_id] {
+                        if !existing.contains(id) {
+                            return Err(GraphError::EntityMissing(*id));
+                        }
+                    }
+                    existing.remove(removed_id);
+                }
+            }
+        }
+        self.add_fact(fact_store);
         Ok(())
     }
 
-    pub fn load_from_file(path: &str) -> std::io::Result<Self> {
-        let content = fs::read_to_string(path)?;
-        let event_log: Vec<Fact> = serde_json::from_str(&content)?;
+    // Folds the duplicate `remove` into `keep`, recorded as an EntityMerged fact so the audit log shows it;
+    //      1. Every edge touching `remove` is re-pointed at `keep`(edges between the two are dropped rather than becoming self-loops).
+    //      2. `keep` gains the properties it lacks from `remove`; on conflicting keys `keep` wins.
+    //      3. `remove` leaves the graph.
+    // Refused, leaving the graph untouched, if either entity is missing or they are the same entity(add_facts_atomic checks both).
+    pub fn merge_entities(&mut self, keep: Uuid, remove: Uuid) -> Result<(), GraphError> {
+        self.add_facts_atomic(FactStore {
+            facts: vec![Fact::EntityMerged { kept_id: keep, removed_id: remove, timestamp: Utc::now() }],
+        })
+    }
 
-        let mut db = GraphDb::new();
-        
-        for fact in event_log.iter() {
-            db.add_fact(FactStore {
-                facts: event_log.clone(),
-            });
+    // Applies an EntityMerged fact as described on merge_entities. Does nothing if either entity is gone.
+    fn apply_merge(&mut self, keep: Uuid, remove: Uuid) {
+        let (Some(&keep_idx), Some(&remove_idx)) = (self.uuid_index_map.get(&keep), self.uuid_index_map.get(&remove)) else {
+            return;
+        };
+        if keep_idx == remove_idx {
+            return;
         }
 
-        Ok(db)
+        let redirect = |id: Uuid| if id == remove { keep } else { id };
+        let moved: Vec<Relationship> = self.graph.edges_directed(remove_idx, petgraph::Direction::Outgoing)
+            .chain(self.graph.edges_directed(remove_idx, petgraph::Direction::Incoming))
+            .map(|edge| edge.weight().clone())
+            .filter(|rel| redirect(rel.source_id) != redirect(rel.target_id))
+            .map(|rel| Relationship { source_id: redirect(rel.source_id), target_id: redirect(rel.target_id), ..rel })
+            .collect();
+
+        let inherited: Vec<(String, String)> = match (self.graph.node_weight(keep_idx), self.graph.node_weight(remove_idx)) {
+            (Some(kept), Some(removed)) => removed.properties.iter()
+                .filter(|(k, _)| !kept.properties.contains_key(*k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        self.remove_entity_node(&remove);
+        for rel in moved {
+            // Both endpoints are known to exist, so this can't fail
+            let _ = self.add_relationship(rel);
+        }
+        if let Some(kept) = self.graph.node_weight_mut(keep_idx) {
+            kept.properties.extend(inherited.iter().cloned());
+        }
+        for (k, v) in &inherited {
+            self.index_property(keep, k, v);
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::BTreeMap;
-    use std::fmt::Debug;
-    use chrono::Local;
-    use super::*;
-    use crate::graph::{EntityType, RelationshipType};
-    use crate::graph::fact::{Fact, FactStore};
-    use chrono::prelude::DateTime;
+    // Drops an entity's node(and with it its edges) and its entries in the lookup indexes.
+    fn remove_entity_node(&mut self, entity_id: &Uuid) {
+        if let Some(&node_idx) = self.uuid_index_map.get(entity_id) {
+            if let Some(entity) = self.graph.remove_node(node_idx) {
+                for (k, v) in &entity.properties {
+                    self.unindex_property(*entity_id, k, v);
+                }
+                for name in std::iter::once(&entity.name).chain(&entity.aliases) {
+                    self.unindex_name(*entity_id, name);
+                }
+            }
+            self.uuid_index_map.remove(entity_id);
+        }
+    }
 
-    #[test]
-    fn test_graph_db_basic_flow() {
-        let mut db = GraphDb::new();
+    // Removes several entities in one batch;
+    //      1. Record a RelationshipInvalidated fact for every connected pair(either direction) touching one of them.
+    //      2. Record an EntityDeleted fact for each.
+    // The log then explains why the edges disappeared instead of leaving them to vanish with their nodes.
+    // Unknown UUIDs are skipped. Returns how many entities were deleted, or why the batch was refused(nothing is deleted then).
+    pub fn delete_entities(&mut self, ids: &[Uuid]) -> Result<usize, GraphError> {
+        let timestamp = Utc::now();
+        let mut pairs = std::collections::BTreeSet::new();
+        let mut deleted = Vec::new();
 
-        let e1_id = Uuid::new_v4();
-        let e2_id = Uuid::new_v4();
+        for id in ids {
+            let Some(&node_idx) = self.uuid_index_map.get(id) else {
+                continue;
+            };
+            let incident = self.graph.edges_directed(node_idx, petgraph::Direction::Outgoing)
+                .chain(self.graph.edges_directed(node_idx, petgraph::Direction::Incoming));
+            for edge in incident {
+                pairs.insert((edge.weight().source_id, edge.weight().target_id));
+            }
+            if !deleted.contains(id) {
+                deleted.push(*id);
+            }
+        }
 
-        let e1_props = {
-            let mut props = BTreeMap::new();
-            props.insert("name".into(), "John Doe".into());
-            props
-        };
+        let mut facts: Vec<Fact> = pairs
+            .into_iter()
+            .map(|(source_id, target_id)| Fact::RelationshipInvalidated { source_id, target_id, timestamp, relationship_type: None })
+            .collect();
+        facts.extend(deleted.iter().map(|&entity_id| Fact::EntityDeleted { entity_id, timestamp }));
+        self.add_facts_atomic(FactStore { facts })?;
 
-        let e2_props = {
-            let mut props = BTreeMap::new();
-            props.insert("name".into(), "Widgets Inc".into());
-            props
-        };
+        Ok(deleted.len())
+    }
 
-        let timestamp = DateTime::from(Local::now());
+    // Extracts a standalone database holding only `entity_ids` and the relationships between them(e.g. a built case, for sharing);
+    //      1. One EntityCreated per kept entity, carrying all of its properties.
+    //      2. One RelationshipAdded per edge with both endpoints kept, with its validity window.
+    // The new event log starts with these facts, stamped now; history before the extraction is not carried over.
+    // Unknown UUIDs are skipped. Fails if the extracted facts don't replay cleanly into the new database.
+    pub fn subgraph(&self, entity_ids: &[Uuid]) -> Result<GraphDb, GraphError> {
+        let timestamp = Utc::now();
+        let keep: std::collections::HashSet<&Uuid> = entity_ids.iter().filter(|id| self.uuid_index_map.contains_key(id)).collect();
 
-        let facts = vec![
-            Fact::EntityCreated {
-                entity_id: e1_id,
-                timestamp,
-                properties: e1_props.clone(),
-            },
-            Fact::EntityCreated {
-                entity_id: e2_id,
-                timestamp,
-                properties: e2_props.clone(),
-            },
-            Fact::RelationshipAdded {
-                source_id: e1_id,
-                target_id: e2_id,
-                relationship_type: RelationshipType::WorksAt.to_string(),
+        let mut facts: Vec<Fact> = Vec::new();
+        for entity in self.entities().filter(|e| keep.contains(&e.id)) {
+            // add_fact derives name and type from the properties, so make sure they're present
+            let mut properties = entity.properties.clone();
+            properties.entry("name".to_string()).or_insert_with(|| entity.name.clone());
+            properties.entry("type".to_string()).or_insert_with(|| entity.entity_type.to_string());
+            facts.push(Fact::EntityCreated { entity_id: entity.id, timestamp, properties });
+        }
+        for rel in self.graph.edge_weights().filter(|r| keep.contains(&r.source_id) && keep.contains(&r.target_id)) {
+            facts.push(Fact::RelationshipAdded {
+                source_id: rel.source_id,
+                target_id: rel.target_id,
+                relationship_type: rel.relationship_type.to_string(),
                 timestamp,
-                valid_from: 2021,
-                valid_to: None,
-            },
-        ];
+                valid_from: rel.valid_from,
+                valid_to: rel.valid_to,
+            });
+        }
+
+        let mut sub = GraphDb::new();
+        sub.add_facts_atomic(FactStore { facts })?;
+        Ok(sub)
+    }
+
+    // Corrects a property value across the whole graph;
+    //      1. Find every entity whose `key` currently equals `from` (via the property index).
+    //      2. Record an EntityUpdated fact setting it to `to`, so the fix is part of the event log.
+    // Returns how many entities were changed, or why the batch was refused(nothing is changed then).
+    pub fn replace_property_value(&mut self, key: &str, from: &str, to: &str) -> Result<usize, GraphError> {
+        if from == to {
+            return Ok(0);
+        }
+        let ids: Vec<Uuid> = self.entities_with_property(key, from).iter().map(|e| e.id).collect();
+        let timestamp = Utc::now();
+
+        let facts: Vec<Fact> = ids
+            .iter()
+            .map(|id| {
+                let mut updated_properties = BTreeMap::new();
+                updated_properties.insert(key.to_string(), to.to_string());
+                Fact::EntityUpdated { entity_id: *id, timestamp, updated_properties }
+            })
+            .collect();
+
+        let changed = facts.len();
+        self.add_facts_atomic(FactStore { facts })?;
+        Ok(changed)
+    }
+
+    // Finds suspicious overlaps for a relationship type meant to be exclusive (e.g. WorksAt);
+    //      1. For each entity, gather its outgoing edges of `rel_type`.
+    //      2. Report every pair of those edges whose validity windows overlap.
+    // Returns (source entity UUID, first relationship, second relationship) per conflicting pair.
+    pub fn temporal_conflicts(&self, rel_type: &str) -> Vec<(Uuid, Relationship, Relationship)> {
+        let mut conflicts = Vec::new();
+
+        for node_idx in self.graph.node_indices() {
+            let edges: Vec<&Relationship> = self
+                .graph
+                .edges_directed(node_idx, petgraph::Direction::Outgoing)
+                .map(|edge| edge.weight())
+                .filter(|rel| rel.relationship_type.to_string() == rel_type)
+                .collect();
+
+            for (i, first) in edges.iter().enumerate() {
+                for second in &edges[i + 1..] {
+                    if first.overlaps(second) {
+                        conflicts.push((first.source_id, (*first).clone(), (*second).clone()));
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    // Splits the event log into per-entity dossiers;
+    //      1. Entity facts go to the entity they describe.
+    //      2. Relationship facts go to both endpoints.
+    // Each entity's facts are in chronological order.
+    pub fn log_by_entity(&self) -> BTreeMap<Uuid, Vec<Fact>> {
+        let mut grouped: BTreeMap<Uuid, Vec<Fact>> = BTreeMap::new();
+
+        for fact in &self.event_log {
+            let involved = match fact {
+                Fact::EntityCreated { entity_id, .. }
+                | Fact::EntityUpdated { entity_id, .. }
+                | Fact::EntityDeleted { entity_id, .. } => vec![*entity_id],
+                Fact::RelationshipAdded { source_id, target_id, .. }
+                | Fact::RelationshipInvalidated { source_id, target_id, .. } => {
+                    if source_id == target_id { vec![*source_id] } else { vec![*source_id, *target_id] }
+                }
+                Fact::EntityMerged { kept_id, removed_id, .. } => vec![*kept_id, *removed_id],
+            };
+            for entity_id in involved {
+                grouped.entry(entity_id).or_default().push(fact.clone());
+            }
+        }
+
+        for facts in grouped.values_mut() {
+            facts.sort_by_key(|fact| fact.timestamp());
+        }
+        grouped
+    }
+
+    // Tallies the event log by fact kind.
+    pub fn lifecycle_summary(&self) -> LifecycleSummary {
+        let mut summary = LifecycleSummary::default();
+        for fact in &self.event_log {
+            match fact {
+                Fact::EntityCreated { .. } => summary.entities_created += 1,
+                Fact::EntityUpdated { .. } => summary.entities_updated += 1,
+                Fact::EntityDeleted { .. } => summary.entities_deleted += 1,
+                Fact::RelationshipAdded { .. } => summary.relationships_added += 1,
+                Fact::RelationshipInvalidated { .. } => summary.relationships_invalidated += 1,
+                Fact::EntityMerged { .. } => summary.entities_merged += 1,
+            }
+        }
+        summary
+    }
+
+    // Iterates over every entity currently in the graph, so callers needn't reach into petgraph.
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.graph.node_weights()
+    }
+
+    // Number of entities currently in the graph(deleted entities don't count).
+    pub fn entity_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Every fact recorded so far, in the order it was added.
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use chrono::Utc;
+    /// use uuid::Uuid;
+    /// use h3imd3ll::graph::GraphDb;
+    /// use h3imd3ll::graph::fact::{Fact, FactStore};
+    ///
+    /// let mut db = GraphDb::new();
+    /// let mut properties = BTreeMap::new();
+    /// properties.insert("name".to_string(), "John".to_string());
+    /// db.add_fact(FactStore {
+    ///     facts: vec![Fact::EntityCreated { entity_id: Uuid::new_v4(), timestamp: Utc::now(), properties }],
+    /// });
+    ///
+    /// for fact in db.facts() {
+    ///     assert!(matches!(fact, Fact::EntityCreated { .. }));
+    /// }
+    /// assert_eq!(db.fact_count(), 1);
+    /// ```
+    pub fn facts(&self) -> &[Fact] {
+        &self.event_log
+    }
+
+    /// Number of facts in the event log.
+    pub fn fact_count(&self) -> usize {
+        self.event_log.len()
+    }
+
+    /// Facts recorded with a sequence number of at least `seq`, in recorded order.
+    ///
+    /// Incremental consumers keep `next_seq()` as a cursor and pass it back next time
+    /// to get only what was recorded in between.
+    pub fn facts_since(&self, seq: u64) -> &[Fact] {
+        let start = self.fact_seqs.partition_point(|&s| s < seq);
+        &self.event_log[start..]
+    }
+
+    /// Every fact in the event log paired with its sequence number, in recorded order.
+    pub fn sequenced_facts(&self) -> impl Iterator<Item = (u64, &Fact)> {
+        self.fact_seqs.iter().copied().zip(&self.event_log)
+    }
+
+    /// Checks the hash chain over the event log, where each fact's hash is
+    /// `sha256(serialized fact || previous fact's hash)`.
+    ///
+    /// Returns the index of the first fact whose stored hash doesn't match, i.e. the first fact
+    /// that was altered, inserted or removed since it was recorded. A log loaded without its chain
+    /// (an older save, a JSON Lines log, or a save whose hashes were removed) can't be vouched for
+    /// and fails at index 0.
+    pub fn verify_integrity(&self) -> Result<(), usize> {
+        if self.unchained {
+            return Err(0);
+        }
+        for (i, fact) in self.event_log.iter().enumerate() {
+            let previous_hash = if i == 0 { "" } else { self.fact_hashes[i - 1].as_str() };
+            if self.fact_hashes.get(i) != Some(&chain_hash(fact, previous_hash)) {
+                return Err(i);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sequence number the next recorded fact will get; sequence numbers are never reused.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    // Stable SHA-256 fingerprint of the event log, as lowercase hex;
+    //      1. Serialize each fact compactly(properties are BTreeMaps, so key order is fixed).
+    //      2. Hash them in log order, one per line.
+    // Any edit, insertion, removal or reordering of facts changes the hash.
+    pub fn log_hash(&self) -> String {
+        hash_facts(&self.event_log)
+    }
+
+    pub fn persist_facts(&self, path: &str) -> std::io::Result<()> {
+        let tmp = self.write_save_beside(path)?;
+        replace_with(&tmp, path)
+    }
+
+    // Writes the save for `path` to its temp file and returns where, leaving `path` itself alone.
+    fn write_save_beside(&self, path: &str) -> std::io::Result<PathBuf> {
+        let save = SaveFile {
+            metadata: LogMetadata {
+                fact_count: self.event_log.len(),
+                log_hash: self.log_hash(),
+                next_seq: self.next_seq,
+                unchained: self.unchained,
+            },
+            facts: self.event_log.clone(),
+            seqs: self.fact_seqs.clone(),
+            hashes: self.fact_hashes.clone(),
+            batches: self.batch_starts.clone(),
+        };
+        let serialized = serde_json::to_string_pretty(&save)?;
+
+        // Write the new contents beside the target and swap them in with one rename,
+        // so a crash mid-write leaves the previous save untouched.
+        let tmp = temp_path(path);
+        let mut file = File::create(&tmp)?;
+        file.write_all(serialized.as_bytes())?;
+        file.sync_all()?;
+        Ok(tmp)
+    }
+
+    // Saves like persist_facts, but shifts the previous file into numbered backups;
+    //      graph_data.json -> graph_data.1.json -> graph_data.2.json ... up to `keep` backups.
+    // The oldest backup beyond `keep` is dropped. A `keep` of 0 means no rotation.
+    // The new save is written out before any backup moves, and the live file is copied rather than moved,
+    // so a failed save leaves both the current file and the backups as they were.
+    pub fn persist_facts_with_rotation(&self, path: &str, keep: usize) -> std::io::Result<()> {
+        let tmp = self.write_save_beside(path)?;
+        if keep > 0 && Path::new(path).exists() {
+            let oldest = backup_path(path, keep);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for n in (1..keep).rev() {
+                let backup = backup_path(path, n);
+                if backup.exists() {
+                    fs::rename(&backup, backup_path(path, n + 1))?;
+                }
+            }
+            fs::copy(path, backup_path(path, 1))?;
+        }
+        replace_with(&tmp, path)
+    }
+
+    // Append-only alternative to persist_facts for long sessions;
+    //      1. Serialize each fact logged since the last flush as one JSON line(JSON Lines).
+    //      2. Append them to `path`, creating it if needed, and advance last_flushed.
+    // Each save costs only the new facts rather than rewriting the whole log.
+    pub fn persist_facts_append(&mut self, path: &str) -> std::io::Result<()> {
+        let mut lines = String::new();
+        for fact in &self.event_log[self.last_flushed..] {
+            lines.push_str(&serde_json::to_string(fact)?);
+            lines.push('\n');
+        }
+
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(lines.as_bytes())?;
+        self.last_flushed = self.event_log.len();
+        Ok(())
+    }
+
+    // Loads a JSON Lines log written by persist_facts_append, one fact per line.
+    // Blank lines are ignored; unreadable lines(e.g. one cut short by a crash) are skipped and noted in load_warnings.
+    // Everything read counts as already flushed, so further appends continue the same file.
+    pub fn load_from_jsonl(path: &str) -> std::io::Result<Self> {
+        let reader = std::io::BufReader::new(File::open(path)?);
+        let mut facts = Vec::new();
+        let mut warnings = Vec::new();
+
+        for (number, line) in std::io::BufRead::lines(reader).enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Fact>(&line) {
+                Ok(fact) => facts.push(fact),
+                Err(e) => warnings.push(format!("Warning: skipped unreadable fact on line {} of {}: {}", number + 1, path, e)),
+            }
+        }
+
+        let mut db = GraphDb::new();
+        db.event_log = facts;
+        warnings.extend(db.rebuild_from_log().iter().map(replay_warning));
+        db.last_flushed = db.event_log.len();
+        db.load_warnings = warnings;
+        Ok(db)
+    }
+
+    pub fn load_from_file(path: &str) -> std::io::Result<Self> {
+        Self::load_from_file_with_aliases(path, HashMap::new())
+    }
+
+    // Loads a persisted log, migrating legacy entity type names through `type_aliases` during replay.
+    pub fn load_from_file_with_aliases(path: &str, type_aliases: HashMap<String, String>) -> std::io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let (metadata, event_log, seqs, hashes, batches) = match serde_json::from_str::<SaveFile>(&content) {
+            Ok(save) => (Some(save.metadata), save.facts, save.seqs, save.hashes, save.batches),
+            // Legacy save: a bare array of facts with no header to verify against
+            Err(_) if content.trim_start().starts_with('[') => {
+                (None, serde_json::from_str::<Vec<Fact>>(&content)?, Vec::new(), Vec::new(), Vec::new())
+            }
+            // Anything else is a damaged save; report what's wrong with it rather than that it isn't an array
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut db = GraphDb::new();
+        db.type_aliases = type_aliases;
+
+        // Check the facts as stored, and pick the sequence counter and chain status back up where the save left it
+        if let Some(metadata) = metadata {
+            db.next_seq = metadata.next_seq;
+            db.unchained = metadata.unchained;
+            let actual = hash_facts(&event_log);
+            if actual != metadata.log_hash {
+                db.load_warnings.push(format!(
+                    "Warning: event log in {} does not match its integrity hash (expected {}, got {}); it may have been modified",
+                    path, metadata.log_hash, actual
+                ));
+            }
+        }
+
+        db.event_log = event_log;
+        db.fact_seqs = seqs;
+        db.fact_hashes = hashes;
+        db.batch_starts = batches;
+        let replay_errors = db.rebuild_from_log();
+        db.load_warnings.extend(replay_errors.iter().map(replay_warning));
+
+        Ok(db)
+    }
+
+    // Undoes the most recent batch, i.e. everything one add_fact call recorded(a fact and its reciprocal edge, a whole import);
+    //      1. Truncate the event log to where the batch started.
+    //      2. Rebuild the graph from the remaining log with rebuild_from_log.
+    // Facts whose batch isn't known(loaded from an older save) undo one at a time.
+    // Returns the undone facts in recorded order; empty if the log is empty.
+    pub fn undo_last(&mut self) -> Vec<Fact> {
+        if self.event_log.is_empty() {
+            return Vec::new();
+        }
+        let start = self.last_batch_start();
+        self.batch_starts.retain(|&batch_start| batch_start < start);
+        let undone = self.event_log.split_off(start);
+        self.fact_seqs.truncate(start);
+        self.fact_hashes.truncate(start);
+        self.rebuild_from_log();
+        self.last_flushed = self.last_flushed.min(self.event_log.len());
+        undone
+    }
+
+    /// The facts `undo_last` would take back, in recorded order.
+    pub fn last_batch(&self) -> &[Fact] {
+        &self.event_log[self.last_batch_start()..]
+    }
+
+    // Where the most recent batch starts in the event log(the last fact alone when no batch covers it).
+    fn last_batch_start(&self) -> usize {
+        let last_fact = self.event_log.len().saturating_sub(1);
+        self.batch_starts.last().copied().filter(|&start| start <= last_fact).unwrap_or(last_fact)
+    }
+
+    // Rebuilds the live graph from the event log alone;
+    //      1. Clear the graph and every index derived from it(UUIDs, names, properties, last activity).
+    //      2. Replay the event log in timestamp order, exactly as loading does.
+    //      3. Keep the log itself, with its sequence numbers and hash chain, in the order it was recorded.
+    // Settings(type aliases, label key, ingest flags) and batch boundaries are kept, but the ingest filters are off during the replay
+    // so every logged fact is applied. Anything changed on the graph directly, bypassing the log, is discarded.
+    // A log without sequence numbers or hashes(e.g. just read from an older save) is numbered in order and gets a fresh chain,
+    // marked as unchained(saved with it, so a later reload keeps the mark) so verify_integrity never vouches for it; stored hashes are otherwise kept as they are, so it can still spot edits.
+    // Returns the errors for logged facts that couldn't be applied(see add_fact).
+    pub fn rebuild_from_log(&mut self) -> Vec<GraphError> {
+        let facts = std::mem::take(&mut self.event_log);
+        let mut seqs = std::mem::take(&mut self.fact_seqs);
+        if seqs.len() != facts.len() {
+            seqs = (0..facts.len() as u64).collect();
+        }
+        let mut hashes = std::mem::take(&mut self.fact_hashes);
+        if hashes.len() != facts.len() {
+            hashes = chain_hashes(&facts);
+            self.unchained = true;
+        }
+        // Never hand out a number the log already uses
+        let next_seq = self.next_seq.max(seqs.iter().max().map_or(0, |seq| seq + 1));
+        let batch_starts = std::mem::take(&mut self.batch_starts);
+        self.graph = StableDiGraph::new();
+        self.uuid_index_map.clear();
+        self.property_index.clear();
+        self.name_index.clear();
+        self.last_activity.clear();
+
+        let (dedup_on_ingest, reject_future_facts) = (self.dedup_on_ingest, self.reject_future_facts);
+        self.dedup_on_ingest = false;
+        self.reject_future_facts = false;
+        let errors = self.replay(&facts, &seqs);
+        self.dedup_on_ingest = dedup_on_ingest;
+        self.reject_future_facts = reject_future_facts;
+
+        // The replay re-recorded every fact in chronological order; restore the log as recorded
+        self.event_log = facts;
+        self.fact_seqs = seqs;
+        self.fact_hashes = hashes;
+        self.batch_starts = batch_starts;
+        self.next_seq = next_seq;
+        errors
+    }
+
+    // Shrinks the event log without changing the graph it produces;
+    //      1. Drop every fact about an entity that was deleted and not re-created(its create, updates, delete and
+    //         the relationship facts touching it). Entities that took part in a merge are kept, since the merge moved their edges.
+    //      2. Fold runs of consecutive EntityUpdated facts for the same entity into one update carrying the latest
+    //         value of every key, stamped with the last update's timestamp. A run only grows while its timestamps don't go
+    //         backwards and no other fact about the entity replays in between, so replay applies the folded update
+    //         exactly where the run used to be.
+    //      3. Rebuild the graph from the compacted log, with a fresh hash chain over it(compaction rewrites history on purpose).
+    // Facts already appended with persist_facts_append no longer line up with the log, so save in full afterwards.
+    pub fn compact_log(&mut self) {
+        let merged: HashSet<Uuid> = self.event_log.iter()
+            .filter_map(|fact| match fact {
+                Fact::EntityMerged { kept_id, removed_id, .. } => Some([*kept_id, *removed_id]),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let gone: Vec<Uuid> = self.event_log.iter()
+            .filter_map(|fact| match fact {
+                Fact::EntityDeleted { entity_id, .. } => Some(*entity_id),
+                _ => None,
+            })
+            .filter(|id| !self.uuid_index_map.contains_key(id) && !merged.contains(id))
+            .collect();
+
+        // Replay position of every entity-level fact, per entity, to tell whether anything else about it lands inside a run
+        let mut entity_keys: HashMap<Uuid, Vec<ReplayKey>> = HashMap::new();
+        for (fact, &seq) in self.event_log.iter().zip(&self.fact_seqs) {
+            let ids = match fact {
+                Fact::EntityCreated { entity_id, .. }
+                | Fact::EntityUpdated { entity_id, .. }
+                | Fact::EntityDeleted { entity_id, .. } => vec![*entity_id],
+                Fact::EntityMerged { kept_id, removed_id, .. } => vec![*kept_id, *removed_id],
+                Fact::RelationshipAdded { .. } | Fact::RelationshipInvalidated { .. } => Vec::new(),
+            };
+            for id in ids {
+                entity_keys.entry(id).or_default().push(replay_key(fact, seq));
+            }
+        }
+        for keys in entity_keys.values_mut() {
+            keys.sort();
+        }
+
+        let mut compacted: Vec<Fact> = Vec::with_capacity(self.event_log.len());
+        let mut compacted_seqs: Vec<u64> = Vec::with_capacity(self.event_log.len());
+        let mut run: Option<(ReplayKey, usize)> = None; // Replay key of the first update folded into compacted's last fact, and how many were folded
+        let seqs = std::mem::take(&mut self.fact_seqs);
+        for (mut fact, seq) in std::mem::take(&mut self.event_log).into_iter().zip(seqs) {
+            if fact.involves_any(&gone) {
+                continue;
+            }
+            let key = replay_key(&fact, seq);
+            let foldable = match (&fact, compacted.last(), run) {
+                (
+                    Fact::EntityUpdated { entity_id, timestamp, .. },
+                    Some(Fact::EntityUpdated { entity_id: previous_id, timestamp: previous_timestamp, .. }),
+                    Some((start, folded)),
+                ) if previous_id == entity_id && timestamp >= previous_timestamp => {
+                    let keys = &entity_keys[entity_id];
+                    keys.partition_point(|k| *k <= key) - keys.partition_point(|k| *k < start) == folded + 1
+                }
+                _ => false,
+            };
+
+            if foldable {
+                // The folded update stands for the run's last fact: its timestamp and sequence number, and every key's latest value
+                if let (Some(Fact::EntityUpdated { updated_properties: earlier, .. }), Fact::EntityUpdated { updated_properties, .. }) = (compacted.pop(), &mut fact) {
+                    let mut merged = earlier;
+                    merged.append(updated_properties);
+                    *updated_properties = merged;
+                }
+                compacted_seqs.pop();
+                run = run.map(|(start, folded)| (start, folded + 1));
+            } else {
+                run = matches!(fact, Fact::EntityUpdated { .. }).then_some((key, 1));
+            }
+            compacted.push(fact);
+            compacted_seqs.push(seq);
+        }
+
+        self.event_log = compacted;
+        self.fact_seqs = compacted_seqs;
+        self.fact_hashes = chain_hashes(&self.event_log);
+        // Batches no longer line up with the compacted log, so what's left undoes one fact at a time
+        self.batch_starts.clear();
+        self.last_flushed = self.last_flushed.min(self.event_log.len());
+        self.rebuild_from_log();
+    }
+
+    // Replays a persisted log in timestamp order, so the rebuilt graph follows chronology rather than file order;
+    //      1. Sort every fact by Fact::timestamp().
+    //      2. Among facts sharing a timestamp, put EntityCreated first so endpoints exist before their edges.
+    //      3. Break any remaining tie by sequence number, i.e. the order the facts were recorded in.
+    //      4. Hold back relationship facts whose endpoints don't exist yet(e.g. an edge stamped earlier than its entities in
+    //         an imported log) and apply them, in order, as soon as an EntityCreated supplies the missing endpoints.
+    // A single chronological pass(rather than all creations up front) keeps create -> delete -> re-create sequences intact,
+    // so deleted evidence can't resurface or vanish depending on interleaving. An invalidation likewise only removes the edges added before it.
+    // Facts still held back at the end are applied anyway, so their errors are reported.
+    fn replay(&mut self, facts: &[Fact], seqs: &[u64]) -> Vec<GraphError> {
+        let mut order: Vec<usize> = (0..facts.len()).collect();
+        order.sort_by_key(|&i| replay_key(&facts[i], seqs[i]));
+
+        let mut errors = Vec::new();
+        let mut waiting: Vec<Fact> = Vec::new();
+        for fact in order.into_iter().map(|i| &facts[i]) {
+            if !self.endpoints_present(fact) {
+                waiting.push(fact.clone());
+                continue;
+            }
+            errors.extend(self.add_fact(FactStore { facts: vec![fact.clone()] }));
+            if matches!(fact, Fact::EntityCreated { .. }) && !waiting.is_empty() {
+                let (ready, still_waiting): (Vec<Fact>, Vec<Fact>) = waiting.into_iter().partition(|f| self.endpoints_present(f));
+                waiting = still_waiting;
+                errors.extend(self.add_fact(FactStore { facts: ready }));
+            }
+        }
+        errors.extend(self.add_fact(FactStore { facts: waiting }));
+        errors
+    }
+
+    // False for a relationship fact while either of its endpoints is missing from the graph; true for every other fact.
+    fn endpoints_present(&self, fact: &Fact) -> bool {
+        match fact {
+            Fact::RelationshipAdded { source_id, target_id, .. }
+            | Fact::RelationshipInvalidated { source_id, target_id, .. } => {
+                self.uuid_index_map.contains_key(source_id) && self.uuid_index_map.contains_key(target_id)
+            }
+            _ => true,
+        }
+    }
+}
+
+// Where a fact falls in replay order(see GraphDb::replay): timestamp, then EntityCreated first, then sequence number.
+type ReplayKey = (DateTime<Utc>, bool, u64);
+
+fn replay_key(fact: &Fact, seq: u64) -> ReplayKey {
+    (fact.timestamp(), !matches!(fact, Fact::EntityCreated { .. }), seq)
+}
+
+// How a fact that couldn't be applied while loading is reported in load_warnings.
+fn replay_warning(error: &GraphError) -> String {
+    format!("Warning: logged fact not applied: {}", error)
+}
+
+// Hashes facts as described on GraphDb::log_hash.
+fn hash_facts(facts: &[Fact]) -> String {
+    let mut hasher = Sha256::new();
+    for fact in facts {
+        // Serializing a Fact can't fail: every field is a plain string, number or map of strings
+        hasher.update(serde_json::to_string(fact).unwrap_or_default().as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// One link of the event log's hash chain: sha256 over the compactly serialized fact followed by the previous link, as lowercase hex.
+fn chain_hash(fact: &Fact, previous_hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_string(fact).unwrap_or_default().as_bytes());
+    hasher.update(previous_hash.as_bytes());
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// The full hash chain over `facts`, starting from an empty previous hash.
+fn chain_hashes(facts: &[Fact]) -> Vec<String> {
+    let mut hashes: Vec<String> = Vec::with_capacity(facts.len());
+    for fact in facts {
+        let previous_hash = hashes.last().map(String::as_str).unwrap_or("");
+        hashes.push(chain_hash(fact, previous_hash));
+    }
+    hashes
+}
+
+// Levenshtein edit distance between two strings, counted in chars(insertions, deletions and substitutions cost 1).
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // Distances from the previous prefix of `a` to every prefix of `b`
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Scratch file persist_facts writes before renaming over `path`, e.g. graph_data.json -> graph_data.tmp.
+fn temp_path(path: &str) -> PathBuf {
+    Path::new(path).with_extension("tmp")
+}
+
+// Swaps a fully written temp file in for `path` with one rename.
+fn replace_with(tmp: &Path, path: &str) -> std::io::Result<()> {
+    // Windows can refuse to rename over an existing file
+    #[cfg(windows)]
+    if Path::new(path).exists() {
+        fs::remove_file(path)?;
+    }
+    fs::rename(tmp, path)
+}
+
+// Name of the n-th rotated backup of `path`, e.g. graph_data.json -> graph_data.2.json.
+fn backup_path(path: &str, n: usize) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+    use chrono::Utc;
+    use super::*;
+    use crate::graph::{EntityType, RelationshipType};
+    use crate::graph::fact::{Fact, FactStore};
+    use crate::test_utils::{add_entities, relate, relate_during, scratch_path};
+    use chrono::prelude::DateTime;
+
+    #[test]
+    fn test_graph_db_basic_flow() {
+        let mut db = GraphDb::new();
+
+        let e1_id = Uuid::new_v4();
+        let e2_id = Uuid::new_v4();
+
+        let e1_props = {
+            let mut props = BTreeMap::new();
+            props.insert("name".into(), "John Doe".into());
+            props
+        };
+
+        let e2_props = {
+            let mut props = BTreeMap::new();
+            props.insert("name".into(), "Widgets Inc".into());
+            props
+        };
+
+        let timestamp = DateTime::from(Utc::now());
+
+        let facts = vec![
+            Fact::EntityCreated {
+                entity_id: e1_id,
+                timestamp,
+                properties: e1_props.clone(),
+            },
+            Fact::EntityCreated {
+                entity_id: e2_id,
+                timestamp,
+                properties: e2_props.clone(),
+            },
+            Fact::RelationshipAdded {
+                source_id: e1_id,
+                target_id: e2_id,
+                relationship_type: RelationshipType::WorksAt.to_string(),
+                timestamp,
+                valid_from: 2021,
+                valid_to: None,
+            },
+        ];
 
         let store = FactStore { facts };
 
@@ -268,4 +1663,1326 @@ mod tests {
         assert_eq!(outgoing[0].name, "Widgets Inc");
         assert_eq!(incoming[0].name, "John Doe");
     }
+
+    #[test]
+    fn test_load_keeps_relationship_logged_before_its_entities() {
+        let e1_id = Uuid::new_v4();
+        let e2_id = Uuid::new_v4();
+        let timestamp = Utc::now();
+
+        let mut e1_props = BTreeMap::new();
+        e1_props.insert("name".to_string(), "John Doe".to_string());
+        let mut e2_props = BTreeMap::new();
+        e2_props.insert("name".to_string(), "Widgets Inc".to_string());
+
+        // The relationship comes first in the file, before either entity exists
+        let event_log = vec![
+            Fact::RelationshipAdded {
+                source_id: e1_id,
+                target_id: e2_id,
+                relationship_type: RelationshipType::WorksAt.to_string(),
+                timestamp,
+                valid_from: 2021,
+                valid_to: None,
+            },
+            Fact::EntityCreated { entity_id: e1_id, timestamp, properties: e1_props },
+            Fact::EntityCreated { entity_id: e2_id, timestamp, properties: e2_props },
+        ];
+
+        let path = scratch_path("json");
+        fs::write(&path, serde_json::to_string(&event_log).unwrap()).unwrap();
+
+        let db = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let outgoing = db.get_outgoing_neighbours(&e1_id);
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].name, "Widgets Inc");
+        assert_eq!(db.event_log.len(), 3);
+    }
+
+    #[test]
+    fn test_load_keeps_relationship_dated_before_its_entities() {
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let day = |d: u32| DateTime::parse_from_rfc3339(&format!("2024-01-0{}T00:00:00Z", d)).unwrap().with_timezone(&Utc);
+        let event_log = vec![
+            Fact::RelationshipAdded {
+                source_id: john,
+                target_id: acme,
+                relationship_type: RelationshipType::WorksAt.to_string(),
+                timestamp: day(1),
+                valid_from: 2021,
+                valid_to: None,
+            },
+            Fact::EntityCreated { entity_id: john, timestamp: day(2), properties: BTreeMap::new() },
+            Fact::EntityCreated { entity_id: acme, timestamp: day(2), properties: BTreeMap::new() },
+        ];
+
+        let path = scratch_path("json");
+        fs::write(&path, serde_json::to_string(&event_log).unwrap()).unwrap();
+
+        let db = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(db.graph.edge_count(), 1);
+        assert!(db.load_warnings.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_on_ingest_skips_identical_fact() {
+        let mut db = GraphDb::new();
+        db.dedup_on_ingest = true;
+
+        let mut props = BTreeMap::new();
+        props.insert("name".to_string(), "John Doe".to_string());
+        let fact = Fact::EntityCreated {
+            entity_id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            properties: props,
+        };
+
+        db.add_fact(FactStore { facts: vec![fact.clone()] });
+        db.add_fact(FactStore { facts: vec![fact] });
+
+        assert_eq!(db.event_log.len(), 1);
+        assert_eq!(db.graph.node_count(), 1);
+    }
+
+    #[test]
+    fn test_property_index_tracks_creates_and_updates() {
+        let mut db = GraphDb::new();
+        let timestamp = Utc::now();
+
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let facts = ids
+            .iter()
+            .zip(["Alice", "Bob", "Carol"])
+            .zip(["London", "London", "Paris"])
+            .map(|((id, name), city)| {
+                let mut props = BTreeMap::new();
+                props.insert("name".to_string(), name.to_string());
+                props.insert("city".to_string(), city.to_string());
+                Fact::EntityCreated { entity_id: *id, timestamp, properties: props }
+            })
+            .collect();
+        db.add_fact(FactStore { facts });
+
+        let mut londoners: Vec<&str> = db.entities_with_property("city", "London").iter().map(|e| e.name.as_str()).collect();
+        londoners.sort();
+        assert_eq!(londoners, vec!["Alice", "Bob"]);
+
+        // Bob moves to Paris
+        let mut updated = BTreeMap::new();
+        updated.insert("city".to_string(), "Paris".to_string());
+        db.add_fact(FactStore {
+            facts: vec![Fact::EntityUpdated { entity_id: ids[1], timestamp, updated_properties: updated }],
+        });
+
+        let londoners = db.entities_with_property("city", "London");
+        assert_eq!(londoners.len(), 1);
+        assert_eq!(londoners[0].name, "Alice");
+        assert_eq!(db.entities_with_property("city", "Paris").len(), 2);
+    }
+
+    #[test]
+    fn test_update_renames_entity_and_moves_name_index() {
+        let mut db = GraphDb::new();
+        let john = Uuid::new_v4();
+        db.add_fact(FactStore {
+            facts: vec![Fact::EntityCreated {
+                entity_id: john,
+                timestamp: Utc::now(),
+                properties: BTreeMap::from([("name".to_string(), "John".to_string()), ("type".to_string(), "Person".to_string())]),
+            }],
+        });
+
+        db.add_fact(FactStore {
+            facts: vec![Fact::EntityUpdated {
+                entity_id: john,
+                timestamp: Utc::now(),
+                updated_properties: BTreeMap::from([("name".to_string(), "Jonathan".to_string())]),
+            }],
+        });
+
+        assert_eq!(db.get_entity(&john).unwrap().name, "Jonathan");
+        assert_eq!(db.find_by_name_ci("jonathan").map(|e| e.id), Some(john));
+        assert!(db.entities_named("John").is_empty());
+    }
+
+    #[test]
+    fn test_update_of_type_property_changes_entity_type() {
+        let mut db = GraphDb::new();
+        let acme = Uuid::new_v4();
+        db.type_aliases.insert("Org".to_string(), "Company".to_string());
+        db.add_fact(FactStore {
+            facts: vec![Fact::EntityCreated {
+                entity_id: acme,
+                timestamp: Utc::now(),
+                properties: BTreeMap::from([("name".to_string(), "Acme".to_string()), ("type".to_string(), "Person".to_string())]),
+            }],
+        });
+
+        let set_type = |label: &str| Fact::EntityUpdated {
+            entity_id: acme,
+            timestamp: Utc::now(),
+            updated_properties: BTreeMap::from([("type".to_string(), label.to_string())]),
+        };
+        db.add_fact(FactStore { facts: vec![set_type("Product")] });
+        assert_eq!(db.get_entity(&acme).unwrap().entity_type, EntityType::Product);
+
+        // Legacy labels go through the type aliases, as on create
+        db.add_fact(FactStore { facts: vec![set_type("Org")] });
+        assert_eq!(db.get_entity(&acme).unwrap().entity_type, EntityType::Company);
+    }
+
+    #[test]
+    fn test_replace_property_value_fixes_every_match() {
+        let mut db = GraphDb::new();
+        let timestamp = Utc::now();
+
+        let facts = [("Alice", "Lodnon"), ("Bob", "Lodnon"), ("Carol", "Paris")]
+            .iter()
+            .map(|(name, city)| {
+                let mut props = BTreeMap::new();
+                props.insert("name".to_string(), name.to_string());
+                props.insert("city".to_string(), city.to_string());
+                Fact::EntityCreated { entity_id: Uuid::new_v4(), timestamp, properties: props }
+            })
+            .collect();
+        db.add_fact(FactStore { facts });
+
+        let changed = db.replace_property_value("city", "Lodnon", "London");
+
+        assert_eq!(changed, Ok(2));
+        assert!(db.entities_with_property("city", "Lodnon").is_empty());
+        assert_eq!(db.entities_with_property("city", "London").len(), 2);
+        assert_eq!(db.entities_with_property("city", "Paris").len(), 1);
+        // Three creations plus one update per fixed entity
+        assert_eq!(db.event_log.len(), 5);
+    }
+
+    #[test]
+    fn test_lifecycle_summary_counts_fact_kinds() {
+        let mut db = GraphDb::new();
+        let timestamp = Utc::now();
+        let ids = add_entities(&mut db, &["A", "B", "C"]);
+        let (a, b, c) = (ids[0], ids[1], ids[2]);
+        relate(&mut db, a, RelationshipType::WorksAt, b);
+
+        let facts = vec![
+            Fact::EntityUpdated { entity_id: a, timestamp, updated_properties: BTreeMap::new() },
+            Fact::EntityUpdated { entity_id: b, timestamp, updated_properties: BTreeMap::new() },
+            Fact::RelationshipInvalidated { source_id: a, target_id: b, timestamp, relationship_type: None },
+            Fact::EntityDeleted { entity_id: c, timestamp },
+        ];
+        db.add_fact(FactStore { facts });
+
+        assert_eq!(
+            db.lifecycle_summary(),
+            LifecycleSummary {
+                entities_created: 3,
+                entities_updated: 2,
+                entities_deleted: 1,
+                relationships_added: 1,
+                relationships_invalidated: 1,
+                entities_merged: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_temporal_conflicts_reports_only_overlapping_windows() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Jane", "Acme", "Widgets"]);
+        let (john, jane, acme, widgets) = (ids[0], ids[1], ids[2], ids[3]);
+
+        // John holds both jobs at once between 2019 and 2020
+        relate_during(&mut db, john, RelationshipType::WorksAt, acme, 2015, Some(2020));
+        relate_during(&mut db, john, RelationshipType::WorksAt, widgets, 2019, None);
+        // Jane moves from one to the other
+        relate_during(&mut db, jane, RelationshipType::WorksAt, acme, 2010, Some(2014));
+        relate_during(&mut db, jane, RelationshipType::WorksAt, widgets, 2015, None);
+
+        let conflicts = db.temporal_conflicts("WorksAt");
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].0, john);
+        assert!(db.temporal_conflicts("LocatedAt").is_empty());
+    }
+
+    #[test]
+    fn test_log_by_entity_files_relationships_under_both_endpoints() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        let (john, acme) = (ids[0], ids[1]);
+        relate(&mut db, john, RelationshipType::WorksAt, acme);
+
+        let dossiers = db.log_by_entity();
+
+        assert_eq!(dossiers.len(), 2);
+        for id in [john, acme] {
+            let facts = &dossiers[&id];
+            assert_eq!(facts.len(), 2);
+            assert!(matches!(facts[1], Fact::RelationshipAdded { .. }));
+        }
+    }
+
+    #[test]
+    fn test_ensure_relationship_is_idempotent() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        let (john, acme) = (ids[0], ids[1]);
+
+        assert_eq!(db.ensure_relationship(john, acme, "WorksAt", 2020, None), Ok(true));
+        assert_eq!(db.ensure_relationship(john, acme, "WorksAt", 2020, None), Ok(false));
+
+        assert_eq!(db.graph.edge_count(), 1);
+        assert_eq!(db.event_log.len(), 3);
+    }
+
+    #[test]
+    fn test_ensure_relationship_reports_what_add_fact_refused() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        let missing = Uuid::new_v4();
+
+        assert_eq!(db.ensure_relationship(ids[0], ids[1], " ", 2020, None), Err(GraphError::BlankRelationshipType(ids[0], ids[1])));
+        assert_eq!(db.ensure_relationship(ids[0], missing, "WorksAt", 2020, None), Err(GraphError::TargetMissing(missing)));
+
+        assert_eq!(db.graph.edge_count(), 0);
+        assert_eq!(db.event_log.len(), 2);
+    }
+
+    #[test]
+    fn test_load_migrates_legacy_entity_types() {
+        let entity_id = Uuid::new_v4();
+        let mut props = BTreeMap::new();
+        props.insert("name".to_string(), "Acme".to_string());
+        props.insert("type".to_string(), "Org".to_string());
+        let event_log = vec![Fact::EntityCreated { entity_id, timestamp: Utc::now(), properties: props }];
+
+        let path = scratch_path("json");
+        fs::write(&path, serde_json::to_string(&event_log).unwrap()).unwrap();
+
+        let mut aliases = HashMap::new();
+        aliases.insert("Org".to_string(), "Company".to_string());
+        let migrated = GraphDb::load_from_file_with_aliases(path.to_str().unwrap(), aliases).unwrap();
+        let plain = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(migrated.get_entity(&entity_id).unwrap().entity_type, EntityType::Company);
+        assert_eq!(plain.get_entity(&entity_id).unwrap().entity_type, EntityType::Custom("Org".to_string()));
+        // The original value is still kept in the properties
+        assert_eq!(migrated.get_entity(&entity_id).unwrap().properties["type"], "Org");
+    }
+
+    #[test]
+    fn test_facts_in_the_same_second_keep_recorded_order_across_reload() {
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+        let props = |name: &str| BTreeMap::from([("name".to_string(), name.to_string())]);
+        let facts = vec![
+            Fact::EntityCreated { entity_id: john, timestamp, properties: props("John") },
+            Fact::EntityUpdated { entity_id: john, timestamp, updated_properties: BTreeMap::from([("phone".to_string(), "555".to_string())]) },
+            Fact::EntityCreated { entity_id: acme, timestamp, properties: props("Acme") },
+        ];
+        let mut db = GraphDb::new();
+        db.add_fact(FactStore { facts: facts.clone() });
+
+        let path = scratch_path("json");
+        db.persist_facts(path.to_str().unwrap()).unwrap();
+        let reloaded = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        let reloaded_again = {
+            reloaded.persist_facts(path.to_str().unwrap()).unwrap();
+            GraphDb::load_from_file(path.to_str().unwrap()).unwrap()
+        };
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.facts(), facts.as_slice());
+        assert_eq!(reloaded_again.facts(), facts.as_slice());
+        assert_eq!(reloaded.fact_seqs, vec![0, 1, 2]);
+
+        let mut sorted: Vec<(u64, &Fact)> = reloaded.sequenced_facts().collect();
+        crate::engine::utils::sort_facts_by_time(&mut sorted);
+        assert!(sorted.into_iter().map(|(_, fact)| fact).eq(&facts));
+    }
+
+    #[test]
+    fn test_sequence_numbers_are_dense_and_survive_reload() {
+        let mut db = GraphDb::new();
+        for name in ["John", "Acme", "London"] {
+            add_entities(&mut db, &[name]);
+        }
+        assert_eq!(db.fact_seqs, vec![0, 1, 2]);
+        assert_eq!(db.next_seq(), 3);
+
+        let path = scratch_path("json");
+        db.persist_facts(path.to_str().unwrap()).unwrap();
+        let mut reloaded = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reloaded.fact_seqs, vec![0, 1, 2]);
+        assert_eq!(reloaded.next_seq(), 3);
+        assert_eq!(reloaded.facts_since(1), &db.facts()[1..]);
+        assert!(reloaded.facts_since(3).is_empty());
+
+        // Numbering carries on from the high-water mark, even after an undo
+        reloaded.undo_last();
+        reloaded.add_fact(FactStore { facts: vec![Fact::EntityDeleted { entity_id: Uuid::new_v4(), timestamp: Utc::now() }] });
+        assert_eq!(reloaded.fact_seqs, vec![0, 1, 3]);
+        assert_eq!(reloaded.facts_since(2).len(), 1);
+    }
+
+    #[test]
+    fn test_verify_integrity_finds_the_altered_fact() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John", "Acme", "London"]);
+        assert_eq!(db.verify_integrity(), Ok(()));
+
+        let path = scratch_path("json");
+        db.persist_facts(path.to_str().unwrap()).unwrap();
+        assert_eq!(GraphDb::load_from_file(path.to_str().unwrap()).unwrap().verify_integrity(), Ok(()));
+
+        // Flip one byte inside the second fact
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replacen("Acme", "Acmf", 1)).unwrap();
+        let tampered = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(tampered.verify_integrity(), Err(1));
+    }
+
+    #[test]
+    fn test_verify_integrity_fails_when_the_chain_is_stripped() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John", "Acme"]);
+        let path = scratch_path("json");
+        db.persist_facts(path.to_str().unwrap()).unwrap();
+
+        // Edit a fact and drop the hashes that would give it away
+        let mut save: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        save.as_object_mut().unwrap().remove("hashes");
+        fs::write(&path, save.to_string().replacen("Acme", "Acmf", 1)).unwrap();
+        let stripped = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+
+        // A bare array of facts has no chain either
+        fs::write(&path, serde_json::to_string(&save["facts"]).unwrap()).unwrap();
+        let legacy = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(stripped.verify_integrity(), Err(0));
+        assert_eq!(legacy.verify_integrity(), Err(0));
+    }
+
+    #[test]
+    fn test_verify_integrity_still_fails_after_resaving_an_unchained_log() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John", "Acme"]);
+        let path = scratch_path("json");
+        fs::write(&path, serde_json::to_string(db.facts()).unwrap()).unwrap();
+
+        // The chain made up on load must not come back as if it had been recorded
+        let legacy = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        legacy.persist_facts(path.to_str().unwrap()).unwrap();
+        let resaved = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(resaved.verify_integrity(), Err(0));
+    }
+
+    #[test]
+    fn test_load_reports_the_save_file_error_for_a_damaged_save() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John"]);
+        let path = scratch_path("json");
+        db.persist_facts(path.to_str().unwrap()).unwrap();
+
+        let mut save: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        save.as_object_mut().unwrap().remove("metadata");
+        fs::write(&path, save.to_string()).unwrap();
+        let error = GraphDb::load_from_file(path.to_str().unwrap()).err().expect("a save without metadata should not load");
+        fs::remove_file(&path).unwrap();
+
+        assert!(error.to_string().contains("metadata"), "unexpected error: {}", error);
+    }
+
+    #[test]
+    fn test_add_facts_atomic_rejects_whole_batch_on_bad_relationship() {
+        let mut db = GraphDb::new();
+        let (john, acme, nobody) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+        let create = |entity_id, name: &str| Fact::EntityCreated {
+            entity_id,
+            timestamp,
+            properties: BTreeMap::from([("name".to_string(), name.to_string())]),
+        };
+        let works_at = |target_id| Fact::RelationshipAdded {
+            source_id: john,
+            target_id,
+            relationship_type: RelationshipType::WorksAt.to_string(),
+            timestamp,
+            valid_from: 2020,
+            valid_to: None,
+        };
+
+        let result = db.add_facts_atomic(FactStore { facts: vec![create(john, "John"), create(acme, "Acme"), works_at(acme), works_at(nobody)] });
+
+        assert_eq!(result, Err(GraphError::TargetMissing(nobody)));
+        assert_eq!(db.graph.node_count(), 0);
+        assert_eq!(db.graph.edge_count(), 0);
+        assert_eq!(db.fact_count(), 0);
+
+        // The same batch without the bad edge goes through, endpoints created earlier in the batch included
+        assert_eq!(db.add_facts_atomic(FactStore { facts: vec![create(john, "John"), create(acme, "Acme"), works_at(acme)] }), Ok(()));
+        assert_eq!(db.graph.edge_count(), 1);
+        assert_eq!(db.fact_count(), 3);
+    }
+
+    #[test]
+    fn test_add_facts_atomic_rejects_whole_batch_on_future_fact() {
+        let mut db = GraphDb::new();
+        db.reject_future_facts = true;
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let future = Utc::now() + chrono::Duration::days(30);
+        let facts = vec![
+            Fact::EntityCreated { entity_id: john, timestamp: Utc::now(), properties: BTreeMap::new() },
+            Fact::EntityCreated { entity_id: acme, timestamp: future, properties: BTreeMap::new() },
+            Fact::RelationshipAdded {
+                source_id: john,
+                target_id: acme,
+                relationship_type: RelationshipType::WorksAt.to_string(),
+                timestamp: Utc::now(),
+                valid_from: 2020,
+                valid_to: None,
+            },
+        ];
+
+        assert_eq!(db.add_facts_atomic(FactStore { facts }), Err(GraphError::FutureFact(future)));
+        assert_eq!(db.graph.node_count(), 0);
+        assert_eq!(db.fact_count(), 0);
+    }
+
+    #[test]
+    fn test_add_fact_applies_mixed_entity_and_relationship_facts() {
+        let mut db = GraphDb::new();
+        let acme = Uuid::new_v4();
+        let mut props = BTreeMap::new();
+        props.insert("name".to_string(), "Acme".to_string());
+        // A second entity already in the graph, so the relationship has both endpoints
+        let john = Uuid::new_v4();
+        db.add_entity(Entity { id: john, name: "John".to_string(), entity_type: EntityType::Person, properties: BTreeMap::new(), aliases: Vec::new() }).unwrap();
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: acme, timestamp: Utc::now(), properties: props },
+                Fact::RelationshipAdded {
+                    source_id: john,
+                    target_id: acme,
+                    relationship_type: RelationshipType::WorksAt.to_string(),
+                    timestamp: Utc::now(),
+                    valid_from: 2020,
+                    valid_to: None,
+                },
+            ],
+        });
+
+        assert!(db.get_entity(&acme).is_some());
+        assert_eq!(db.get_outgoing_neighbours(&john)[0].id, acme);
+        assert_eq!(db.fact_count(), 2);
+    }
+
+    #[test]
+    fn test_rebuild_from_log_discards_direct_graph_changes() {
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+        let facts: Vec<Fact> = [(john, "John"), (acme, "Acme")]
+            .iter()
+            .map(|(id, name)| {
+                let mut properties = BTreeMap::new();
+                properties.insert("name".to_string(), name.to_string());
+                Fact::EntityCreated { entity_id: *id, timestamp, properties }
+            })
+            .chain(std::iter::once(Fact::RelationshipAdded {
+                source_id: john,
+                target_id: acme,
+                relationship_type: RelationshipType::WorksAt.to_string(),
+                timestamp,
+                valid_from: 2020,
+                valid_to: None,
+            }))
+            .collect();
+
+        let mut canonical = GraphDb::new();
+        canonical.add_fact(FactStore { facts: facts.clone() });
+        let mut db = GraphDb::new();
+        db.add_fact(FactStore { facts });
+
+        // Mutate the graph behind the log's back
+        let stray = Entity { id: Uuid::new_v4(), name: "Stray".to_string(), entity_type: EntityType::Person, properties: BTreeMap::new(), aliases: Vec::new() };
+        db.add_entity(stray).unwrap();
+        let john_node = db.uuid_index_map[&john];
+        db.graph.remove_node(john_node);
+
+        db.rebuild_from_log();
+
+        let ids = |db: &GraphDb| {
+            let mut ids: Vec<Uuid> = db.entities().map(|e| e.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&db), ids(&canonical));
+        assert_eq!(db.graph.edge_count(), canonical.graph.edge_count());
+        assert!(db.find_by_name_ci("Stray").is_none());
+        assert_eq!(db.find_by_name_ci("john").map(|e| e.id), Some(john));
+        assert_eq!(db.fact_count(), canonical.fact_count());
+    }
+
+    #[test]
+    fn test_compact_log_folds_updates_and_keeps_final_state() {
+        let mut db = GraphDb::new();
+        let (john, ghost) = (Uuid::new_v4(), Uuid::new_v4());
+        let timestamp = Utc::now();
+        let props = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<BTreeMap<_, _>>();
+
+        db.add_fact(FactStore {
+            facts: vec![
+                Fact::EntityCreated { entity_id: john, timestamp, properties: props(&[("name", "John")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp, updated_properties: props(&[("phone", "555"), ("city", "Paris")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp, updated_properties: props(&[("city", "London")]) },
+                Fact::EntityUpdated { entity_id: john, timestamp, updated_properties: props(&[("phone", "556")]) },
+                Fact::EntityCreated { entity_id: ghost, timestamp, properties: props(&[("name", "Ghost")]) },
+                Fact::EntityDeleted { entity_id: ghost, timestamp },
+            ],
+        });
+        let before = db.get_entity(&john).unwrap().properties.clone();
+
+        db.compact_log();
+
+        let updates = db.facts().iter().filter(|f| matches!(f, Fact::EntityUpdated { .. })).count();
+        assert_eq!(updates, 1);
+        assert_eq!(db.fact_count(), 2);
+        assert_eq!(db.get_entity(&john).unwrap().properties, before);
+        assert_eq!(before["phone"], "556");
+        assert_eq!(before["city"], "London");
+
+        // The compacted log alone reproduces the same graph
+        db.rebuild_from_log();
+        assert_eq!(db.get_entity(&john).unwrap().properties, before);
+        assert_eq!(db.entity_count(), 1);
+    }
+
+    #[test]
+    fn test_compact_log_keeps_state_of_updates_recorded_out_of_time_order() {
+        let mut db = GraphDb::new();
+        let (john, jane) = (Uuid::new_v4(), Uuid::new_v4());
+        let at = |secs: i64| DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::seconds(secs);
+        let props = |key: &str, value: &str| BTreeMap::from([(key.to_string(), value.to_string())]);
+        let update = |entity_id, secs, key: &str, value: &str| Fact::EntityUpdated { entity_id, timestamp: at(secs), updated_properties: props(key, value) };
+
+        // An imported log: John's second update is stamped earlier than his first, and Jane's
+        // consecutive updates straddle another update of hers recorded later
+        db.event_log = vec![
+            Fact::EntityCreated { entity_id: john, timestamp: at(0), properties: props("name", "John") },
+            Fact::EntityCreated { entity_id: jane, timestamp: at(0), properties: props("name", "Jane") },
+            update(john, 10, "city", "London"),
+            update(john, 5, "city", "Paris"),
+            update(jane, 10, "city", "London"),
+            update(jane, 20, "zip", "N1"),
+            update(john, 30, "phone", "555"),
+            update(jane, 15, "city", "Rome"),
+        ];
+        db.rebuild_from_log();
+        let before: Vec<BTreeMap<String, String>> = [john, jane].iter().map(|id| db.get_entity(id).unwrap().properties.clone()).collect();
+        assert_eq!(before[0]["city"], "London");
+        assert_eq!(before[1]["city"], "Rome");
+
+        db.compact_log();
+
+        let after: Vec<BTreeMap<String, String>> = [john, jane].iter().map(|id| db.get_entity(id).unwrap().properties.clone()).collect();
+        assert_eq!(after, before);
+        assert_eq!(db.fact_count(), 8);
+    }
+
+    #[test]
+    fn test_io_balance_counts_edges_by_direction() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme", "Widgets", "Jane"]);
+
+        // ids[0] points at the other three, and ids[3] points back at it once
+        for (source_id, target_id) in [(ids[0], ids[1]), (ids[0], ids[2]), (ids[0], ids[3]), (ids[3], ids[0])] {
+            relate(&mut db, source_id, RelationshipType::WorksAt, target_id);
+        }
+
+        assert_eq!(db.io_balance(&ids[0]), (1, 3));
+        assert_eq!(db.io_balance(&ids[1]), (1, 0));
+        assert_eq!(db.io_balance(&Uuid::new_v4()), (0, 0));
+    }
+
+    #[test]
+    fn test_co_related_recommends_colleagues() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Jane", "Bob", "Acme", "Widgets"]);
+        let (john, jane, bob, acme, widgets) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+        for (source_id, target_id) in [(john, acme), (jane, acme), (bob, widgets)] {
+            relate(&mut db, source_id, RelationshipType::WorksAt, target_id);
+        }
+
+        let johns: Vec<&str> = db.co_related(&john, "WorksAt").iter().map(|e| e.name.as_str()).collect();
+        let janes: Vec<&str> = db.co_related(&jane, "WorksAt").iter().map(|e| e.name.as_str()).collect();
+
+        assert_eq!(johns, vec!["Jane"]);
+        assert_eq!(janes, vec!["John"]);
+        assert!(db.co_related(&bob, "WorksAt").is_empty());
+    }
+
+    #[test]
+    fn test_save_rotation_keeps_last_k_backups() {
+        let dir = std::env::temp_dir().join(format!("h3imd3ll_rotation_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("graph_data.json");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        let keep = 2;
+        for _ in 0..=keep {
+            add_entities(&mut db, &["John"]);
+            db.persist_facts_with_rotation(path, keep).unwrap();
+        }
+        // One more save pushes the oldest backup out
+        db.persist_facts_with_rotation(path, keep).unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["graph_data.1.json", "graph_data.2.json", "graph_data.json"]);
+
+        // The most recent backup is the previous save (3 facts), the current file has all of them
+        assert_eq!(GraphDb::load_from_file(backup_path(path, 1).to_str().unwrap()).unwrap().event_log.len(), 3);
+        assert_eq!(GraphDb::load_from_file(path).unwrap().event_log.len(), 3);
+        assert_eq!(GraphDb::load_from_file(backup_path(path, 2).to_str().unwrap()).unwrap().event_log.len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_rotated_save_keeps_the_current_file() {
+        let dir = std::env::temp_dir().join(format!("h3imd3ll_rotation_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("graph_data.json");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John"]);
+        db.persist_facts_with_rotation(path, 2).unwrap();
+
+        // A directory where the temp file goes makes the next save fail before anything is renamed
+        add_entities(&mut db, &["Acme"]);
+        fs::create_dir(temp_path(path)).unwrap();
+        assert!(db.persist_facts_with_rotation(path, 2).is_err());
+
+        assert_eq!(GraphDb::load_from_file(path).unwrap().event_log.len(), 1);
+        assert!(!backup_path(path, 1).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merged_relationship_counts_edges_per_type() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Jane"]);
+        let (john, jane) = (ids[0], ids[1]);
+
+        for rel in [RelationshipType::WorksAt, RelationshipType::WorksAt, RelationshipType::Knows] {
+            relate(&mut db, john, rel, jane);
+        }
+
+        assert_eq!(
+            db.merged_relationship(&john, &jane),
+            vec![("Knows".to_string(), 1), ("WorksAt".to_string(), 2)]
+        );
+        assert!(db.merged_relationship(&jane, &john).is_empty());
+        // The underlying multigraph is untouched
+        assert_eq!(db.graph.edge_count(), 3);
+    }
+
+    #[test]
+    fn test_reject_future_facts_flag() {
+        let future = Utc::now() + chrono::Duration::days(30);
+        let fact = Fact::EntityCreated { entity_id: Uuid::new_v4(), timestamp: future, properties: BTreeMap::new() };
+
+        let mut strict = GraphDb::new();
+        strict.reject_future_facts = true;
+        assert_eq!(strict.add_fact(FactStore { facts: vec![fact.clone()] }), vec![GraphError::FutureFact(future)]);
+        assert!(strict.event_log.is_empty());
+        assert_eq!(strict.graph.node_count(), 0);
+
+        // Facts within the tolerance still go through
+        strict.add_fact(FactStore {
+            facts: vec![Fact::EntityCreated { entity_id: Uuid::new_v4(), timestamp: Utc::now(), properties: BTreeMap::new() }],
+        });
+        assert_eq!(strict.event_log.len(), 1);
+
+        let mut lenient = GraphDb::new();
+        lenient.add_fact(FactStore { facts: vec![fact] });
+        assert_eq!(lenient.event_log.len(), 1);
+    }
+
+    #[test]
+    fn test_neighbours_via_any_unions_listed_types_only() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme", "London", "Jane"]);
+        let (john, acme, london, jane) = (ids[0], ids[1], ids[2], ids[3]);
+        for (target_id, rel) in [(acme, RelationshipType::WorksAt), (london, RelationshipType::LocatedAt), (jane, RelationshipType::Knows)] {
+            relate(&mut db, john, rel, target_id);
+        }
+
+        let rel_types = vec!["WorksAt".to_string(), "LocatedAt".to_string()];
+        let mut names: Vec<&str> = db.neighbours_via_any(&john, &rel_types).iter().map(|e| e.name.as_str()).collect();
+        names.sort();
+
+        assert_eq!(names, vec!["Acme", "London"]);
+        assert!(db.neighbours_via_any(&john, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_log_hash_verifies_unmodified_load_and_flags_edits() {
+        let path = scratch_path("json");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John"]);
+        db.persist_facts(path).unwrap();
+
+        let loaded = GraphDb::load_from_file(path).unwrap();
+        assert!(loaded.load_warnings.is_empty());
+        assert_eq!(loaded.log_hash(), db.log_hash());
+
+        // Tamper with a fact but leave the stored hash alone
+        let tampered = fs::read_to_string(path).unwrap().replace("\"John\"", "\"Jane\"");
+        fs::write(path, tampered).unwrap();
+
+        let loaded = GraphDb::load_from_file(path).unwrap();
+        assert_eq!(loaded.load_warnings.len(), 1);
+        assert!(loaded.load_warnings[0].contains("integrity hash"));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_second_degree_returns_only_two_hop_nodes() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Jane", "Acme", "Bob", "London", "Eve"]);
+        // John -> Jane, John -> Acme, Bob -> Acme, Jane -> Acme, Acme -> London, London -> Eve
+        for (source, target, rel) in [
+            (0, 1, RelationshipType::Knows),
+            (0, 2, RelationshipType::WorksAt),
+            (3, 2, RelationshipType::WorksAt),
+            (1, 2, RelationshipType::WorksAt),
+            (2, 4, RelationshipType::LocatedAt),
+            (4, 5, RelationshipType::Knows),
+        ] {
+            relate(&mut db, ids[source], rel, ids[target]);
+        }
+
+        // Jane is both one and two hops away, so she only counts as direct; Eve is three hops
+        let mut found: Vec<&str> = db.second_degree(&ids[0]).iter().map(|e| e.name.as_str()).collect();
+        found.sort();
+        assert_eq!(found, vec!["Bob", "London"]);
+        assert!(db.second_degree(&Uuid::new_v4()).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_edges_reports_repeated_same_type_edges() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme", "London"]);
+        let (john, acme, london) = (ids[0], ids[1], ids[2]);
+
+        for (target_id, rel) in [
+            (acme, RelationshipType::WorksAt),
+            (acme, RelationshipType::WorksAt),
+            (acme, RelationshipType::Knows),
+            (london, RelationshipType::LocatedAt),
+        ] {
+            relate(&mut db, john, rel, target_id);
+        }
+
+        assert_eq!(db.duplicate_edges(), vec![(john, acme, "WorksAt".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_load_replays_relationship_facts_in_timestamp_order() {
+        let (john, acme) = (Uuid::new_v4(), Uuid::new_v4());
+        let base = Utc::now() - chrono::Duration::hours(3);
+        let at = |hours: i64| base + chrono::Duration::hours(hours);
+        let works_at = |timestamp, valid_from| Fact::RelationshipAdded {
+            source_id: john,
+            target_id: acme,
+            relationship_type: RelationshipType::WorksAt.to_string(),
+            timestamp,
+            valid_from,
+            valid_to: None,
+        };
+
+        // Chronologically: add(2019) -> invalidate -> add(2023). The file lists them scrambled.
+        let event_log = vec![
+            works_at(at(2), 2023),
+            Fact::RelationshipInvalidated { source_id: john, target_id: acme, timestamp: at(1), relationship_type: None },
+            Fact::EntityCreated { entity_id: john, timestamp: base, properties: BTreeMap::new() },
+            works_at(at(0), 2019),
+            Fact::EntityCreated { entity_id: acme, timestamp: base, properties: BTreeMap::new() },
+        ];
+
+        let path = scratch_path("json");
+        fs::write(&path, serde_json::to_string(&event_log).unwrap()).unwrap();
+        let db = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Only the relationship added after the invalidation survives
+        let edges: Vec<&Relationship> = db.graph.edge_weights().collect();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].valid_from, 2023);
+    }
+
+    #[test]
+    fn test_similar_entities_ranks_shared_neighbours_and_properties_highest() {
+        let mut db = GraphDb::new();
+        let timestamp = Utc::now();
+        let (john, jon, bob, acme, london) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let person = |name: &str, city: &str| {
+            let mut props = BTreeMap::new();
+            props.insert("name".to_string(), name.to_string());
+            props.insert("type".to_string(), "Person".to_string());
+            props.insert("city".to_string(), city.to_string());
+            props.insert("phone".to_string(), if city == "London" { "555".to_string() } else { "777".to_string() });
+            props
+        };
+        let facts = vec![
+            Fact::EntityCreated { entity_id: john, timestamp, properties: person("John", "London") },
+            Fact::EntityCreated { entity_id: jon, timestamp, properties: person("Jon", "London") },
+            Fact::EntityCreated { entity_id: bob, timestamp, properties: person("Bob", "Paris") },
+            Fact::EntityCreated { entity_id: acme, timestamp, properties: BTreeMap::new() },
+            Fact::EntityCreated { entity_id: london, timestamp, properties: BTreeMap::new() },
+        ];
+        db.add_fact(FactStore { facts });
+        // John and Jon share both neighbours; Bob only shares Acme
+        for (source_id, target_id) in [(john, acme), (john, london), (jon, acme), (jon, london), (bob, acme)] {
+            relate(&mut db, source_id, RelationshipType::Knows, target_id);
+        }
+
+        let similar = db.similar_entities(&john, 2);
+
+        assert_eq!(similar.len(), 2);
+        assert_eq!(similar[0].0, jon);
+        assert!(similar[0].1 > 0.6);
+        assert_eq!(similar[1].0, bob);
+        assert!(similar[1].1 < similar[0].1);
+    }
+
+    #[test]
+    fn test_custom_relationship_type_survives_persist_and_load() {
+        let path = scratch_path("json");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["Official", "Contractor"]);
+        relate(&mut db, ids[1], RelationshipType::Custom("Bribed".to_string()), ids[0]);
+        db.persist_facts(path).unwrap();
+
+        let loaded = GraphDb::load_from_file(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let edges: Vec<&Relationship> = loaded.graph.edge_weights().collect();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].relationship_type, RelationshipType::Custom("Bribed".to_string()));
+        assert_eq!(edges[0].relationship_type.to_string(), "Bribed");
+    }
+
+    #[test]
+    fn test_add_relationship_reports_missing_endpoint() {
+        let mut db = GraphDb::new();
+        let (known, unknown) = (add_entities(&mut db, &["John"])[0], Uuid::new_v4());
+        let relationship = |source_id, target_id| Relationship {
+            source_id,
+            target_id,
+            relationship_type: RelationshipType::Knows,
+            valid_from: 2020,
+            valid_to: None,
+        };
+
+        assert_eq!(db.add_relationship(relationship(known, unknown)), Err(GraphError::TargetMissing(unknown)));
+        assert_eq!(db.add_relationship(relationship(unknown, known)), Err(GraphError::SourceMissing(unknown)));
+        assert_eq!(db.graph.edge_count(), 0);
+
+        assert_eq!(db.add_relationship(relationship(known, known)), Ok(()));
+        assert_eq!(db.graph.edge_count(), 1);
+    }
+
+    #[test]
+    fn test_add_fact_returns_errors_for_facts_that_did_not_apply() {
+        let mut db = GraphDb::new();
+        let (known, unknown) = (Uuid::new_v4(), Uuid::new_v4());
+        let created = Fact::EntityCreated { entity_id: known, timestamp: Utc::now(), properties: BTreeMap::new() };
+        assert!(db.add_fact(FactStore { facts: vec![created.clone()] }).is_empty());
+
+        let edge = |target_id, relationship_type: &str| Fact::RelationshipAdded {
+            source_id: known,
+            target_id,
+            relationship_type: relationship_type.to_string(),
+            timestamp: Utc::now(),
+            valid_from: 2020,
+            valid_to: None,
+        };
+        let errors = db.add_fact(FactStore { facts: vec![created, edge(unknown, "Knows"), edge(known, " ")] });
+
+        assert_eq!(errors, vec![
+            GraphError::DuplicateEntity(known),
+            GraphError::TargetMissing(unknown),
+            GraphError::BlankRelationshipType(known, known),
+        ]);
+        // The facts are still logged, only the graph is unchanged
+        assert_eq!(db.fact_count(), 4);
+        assert_eq!(db.graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn test_add_entity_rejects_duplicate_uuid() {
+        let mut db = GraphDb::new();
+        let mut properties = BTreeMap::new();
+        properties.insert("name".to_string(), "John".to_string());
+        let entity = Entity { id: Uuid::new_v4(), name: "John".to_string(), entity_type: EntityType::Person, properties, aliases: Vec::new() };
+
+        let node_index = db.add_entity(entity.clone()).unwrap();
+        assert_eq!(db.graph.node_weight(node_index).unwrap().id, entity.id);
+
+        let renamed = Entity { name: "Jane".to_string(), ..entity.clone() };
+        assert_eq!(db.add_entity(renamed), Err(GraphError::DuplicateEntity(entity.id)));
+        assert_eq!(db.graph.node_count(), 1);
+        assert_eq!(db.get_entity(&entity.id).unwrap().name, "John");
+    }
+
+    #[test]
+    fn test_load_replays_entity_lifecycle_in_timestamp_order() {
+        let (deleted, recreated) = (Uuid::new_v4(), Uuid::new_v4());
+        let base = Utc::now() - chrono::Duration::hours(5);
+        let at = |hours: i64| base + chrono::Duration::hours(hours);
+        let mut props = BTreeMap::new();
+        props.insert("name".to_string(), "John".to_string());
+        let mut updated_properties = BTreeMap::new();
+        updated_properties.insert("phone".to_string(), "555".to_string());
+
+        // create -> update -> delete, and create -> delete -> create again, written to the file out of order
+        let event_log = vec![
+            Fact::EntityDeleted { entity_id: deleted, timestamp: at(2) },
+            Fact::EntityCreated { entity_id: recreated, timestamp: at(4), properties: props.clone() },
+            Fact::EntityUpdated { entity_id: deleted, timestamp: at(1), updated_properties },
+            Fact::EntityDeleted { entity_id: recreated, timestamp: at(3) },
+            Fact::EntityCreated { entity_id: deleted, timestamp: at(0), properties: props.clone() },
+            Fact::EntityCreated { entity_id: recreated, timestamp: at(0), properties: props },
+        ];
+
+        let path = scratch_path("json");
+        fs::write(&path, serde_json::to_string(&event_log).unwrap()).unwrap();
+        let db = GraphDb::load_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(db.get_entity(&deleted).is_none());
+        assert!(db.get_entity(&recreated).is_some());
+        assert_eq!(db.graph.node_count(), 1);
+        assert_eq!(db.event_log.len(), 6);
+    }
+
+    #[test]
+    fn test_interrupted_save_leaves_previous_file_intact() {
+        let path = scratch_path("json");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John"]);
+        db.persist_facts(path).unwrap();
+        assert!(!temp_path(path).exists());
+
+        // A save that died halfway leaves a truncated temp file behind, but never touches the target
+        fs::write(temp_path(path), "{\"metadata\": {\"fact_co").unwrap();
+        let reloaded = GraphDb::load_from_file(path).unwrap();
+        assert_eq!(reloaded.event_log.len(), 1);
+        assert!(reloaded.load_warnings.is_empty());
+
+        // The next save simply replaces the stale temp file
+        db.persist_facts(path).unwrap();
+        assert!(!temp_path(path).exists());
+        assert_eq!(GraphDb::load_from_file(path).unwrap().event_log.len(), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_append_flushes_only_new_facts_and_reloads_them_all() {
+        let path = scratch_path("jsonl");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        let mut ids = add_entities(&mut db, &["John", "Acme"]);
+        db.persist_facts_append(path).unwrap();
+        assert_eq!(db.last_flushed, 2);
+
+        ids.extend(add_entities(&mut db, &["London"]));
+        db.persist_facts_append(path).unwrap();
+
+        // Each fact was written exactly once
+        assert_eq!(fs::read_to_string(path).unwrap().lines().count(), 3);
+
+        let reloaded = GraphDb::load_from_jsonl(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(reloaded.event_log.len(), 3);
+        assert_eq!(reloaded.last_flushed, 3);
+        assert!(ids.iter().all(|id| reloaded.get_entity(id).is_some()));
+    }
+
+    #[test]
+    fn test_shortest_path_follows_edge_direction() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["A", "B", "C"]);
+        let (a, b, c) = (ids[0], ids[1], ids[2]);
+        for (source_id, target_id) in [(a, b), (b, c)] {
+            relate(&mut db, source_id, RelationshipType::Knows, target_id);
+        }
+
+        assert_eq!(db.shortest_path(a, c), Some(vec![a, b, c]));
+        assert_eq!(db.shortest_path(a, a), Some(vec![a]));
+        // Against the edges there is no route
+        assert_eq!(db.shortest_path(c, a), None);
+    }
+
+    #[test]
+    fn test_all_paths_finds_both_sides_of_a_diamond() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["A", "B", "C", "D"]);
+        let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+        // D -> A closes a cycle that the search must not follow round
+        for (source_id, target_id) in [(a, b), (b, d), (a, c), (c, d), (d, a)] {
+            relate(&mut db, source_id, RelationshipType::Knows, target_id);
+        }
+
+        let mut paths = db.all_paths(a, d, 5);
+        paths.sort_by_key(|p| p[1] == c);
+
+        assert_eq!(paths, vec![vec![a, b, d], vec![a, c, d]]);
+        // Neither route fits in a single hop
+        assert!(db.all_paths(a, d, 1).is_empty());
+    }
+
+    #[test]
+    fn test_entity_count_matches_distinct_entities() {
+        let mut db = GraphDb::new();
+        let ids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        let mut facts: Vec<Fact> = ids
+            .iter()
+            .map(|id| Fact::EntityCreated { entity_id: *id, timestamp: Utc::now(), properties: BTreeMap::new() })
+            .collect();
+        // A repeated creation of the same UUID is rejected, not counted twice
+        facts.push(Fact::EntityCreated { entity_id: ids[0], timestamp: Utc::now(), properties: BTreeMap::new() });
+        db.add_fact(FactStore { facts });
+
+        assert_eq!(db.entity_count(), 3);
+        let mut listed: Vec<Uuid> = db.entities().map(|e| e.id).collect();
+        listed.sort();
+        let mut expected = ids.to_vec();
+        expected.sort();
+        assert_eq!(listed, expected);
+    }
+
+    #[test]
+    fn test_find_by_name_ci_ignores_case() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "JOHN"]);
+
+        assert_eq!(db.find_by_name_ci("john").map(|e| e.id), Some(ids[0]));
+        assert!(db.find_by_name_ci("jon").is_none());
+    }
+
+    #[test]
+    fn test_find_all_by_name_returns_every_namesake() {
+        let mut db = GraphDb::new();
+        let namesakes = add_entities(&mut db, &["John Smith", "John Smith"]);
+
+        let ids: Vec<Uuid> = db.find_all_by_name("john smith").iter().map(|e| e.id).collect();
+        assert_eq!(ids, namesakes);
+    }
+
+    #[test]
+    fn test_relationships_valid_at_respects_validity_window() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        relate_during(&mut db, ids[0], RelationshipType::WorksAt, ids[1], 2020, Some(2022));
+
+        assert_eq!(db.relationships_valid_at(2021).len(), 1);
+        assert_eq!(db.relationships_valid_at(2022).len(), 1);
+        assert!(db.relationships_valid_at(2024).is_empty());
+        assert!(db.relationships_valid_at(2019).is_empty());
+    }
+
+    #[test]
+    fn test_typed_invalidation_removes_only_that_relationship() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme"]);
+        let (john, acme) = (ids[0], ids[1]);
+        for rel in [RelationshipType::WorksAt, RelationshipType::LocatedAt] {
+            relate(&mut db, john, rel, acme);
+        }
+        db.add_fact(FactStore {
+            facts: vec![Fact::RelationshipInvalidated {
+                source_id: john,
+                target_id: acme,
+                timestamp: Utc::now(),
+                relationship_type: Some(RelationshipType::WorksAt.to_string()),
+            }],
+        });
+
+        assert_eq!(db.merged_relationship(&john, &acme), vec![("LocatedAt".to_string(), 1)]);
+
+        // Untyped invalidation still clears whatever is left
+        db.add_fact(FactStore {
+            facts: vec![Fact::RelationshipInvalidated { source_id: john, target_id: acme, timestamp: Utc::now(), relationship_type: None }],
+        });
+        assert!(db.merged_relationship(&john, &acme).is_empty());
+    }
+
+    #[test]
+    fn test_hub_degree_and_most_connected() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Jane", "Bob", "Eve"]);
+        for target_id in &ids[1..] {
+            relate(&mut db, ids[0], RelationshipType::Knows, *target_id);
+        }
+
+        assert_eq!(db.entity_degree(&ids[0]), Some((0, 3)));
+        assert_eq!(db.entity_degree(&ids[1]), Some((1, 0)));
+        assert_eq!(db.entity_degree(&Uuid::new_v4()), None);
+        assert_eq!(db.most_connected(1), vec![(ids[0], 3)]);
+        assert_eq!(db.most_connected(10).len(), 4);
+    }
+
+    #[test]
+    fn test_connected_components_separates_disjoint_pairs() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Jane", "Bob", "Eve"]);
+        // Opposite directions, to show direction doesn't split a pair
+        for (source_id, target_id) in [(ids[0], ids[1]), (ids[3], ids[2])] {
+            relate(&mut db, source_id, RelationshipType::Knows, target_id);
+        }
+
+        let mut components = db.connected_components();
+        for component in components.iter_mut() {
+            component.sort();
+        }
+        components.sort();
+
+        let mut expected = vec![vec![ids[0], ids[1]], vec![ids[2], ids[3]]];
+        for component in expected.iter_mut() {
+            component.sort();
+        }
+        expected.sort();
+        assert_eq!(components, expected);
+    }
+
+    #[test]
+    fn test_subgraph_keeps_only_selected_entities_and_their_edges() {
+        let mut db = GraphDb::new();
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+        let facts = ids
+            .iter()
+            .map(|id| {
+                let mut properties = BTreeMap::new();
+                properties.insert("name".to_string(), id.to_string());
+                properties.insert("country".to_string(), "US".to_string());
+                Fact::EntityCreated { entity_id: *id, timestamp: Utc::now(), properties }
+            })
+            .collect();
+        db.add_fact(FactStore { facts });
+        for pair in ids.windows(2) {
+            relate(&mut db, pair[0], RelationshipType::Knows, pair[1]);
+        }
+
+        let sub = db.subgraph(&ids[1..3]).unwrap();
+
+        assert_eq!(sub.entity_count(), 2);
+        assert_eq!(sub.graph.edge_count(), 1);
+        assert_eq!(sub.get_entity(&ids[1]).unwrap().properties["country"], "US");
+        assert_eq!(sub.fact_count(), 3);
+        // The source database is untouched
+        assert_eq!(db.entity_count(), 4);
+    }
+
+    #[test]
+    fn test_fuzzy_find_ranks_near_misses_by_distance() {
+        let mut db = GraphDb::new();
+        add_entities(&mut db, &["John Doe", "Joan Dow", "Acme"]);
+
+        let matches: Vec<(&str, usize)> = db.fuzzy_find("Jon Doe", 2).iter().map(|(e, d)| (e.name.as_str(), *d)).collect();
+        assert_eq!(matches, vec![("John Doe", 1), ("Joan Dow", 2)]);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_merge_entities_moves_relationships_and_properties_to_kept() {
+        let mut db = GraphDb::new();
+        let (john, johnny, acme, london) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let facts = [(john, "John", "555"), (johnny, "Johnny", "999"), (acme, "Acme", ""), (london, "London", "")]
+            .iter()
+            .map(|(id, name, phone)| {
+                let mut properties = BTreeMap::new();
+                properties.insert("name".to_string(), name.to_string());
+                properties.insert("phone".to_string(), phone.to_string());
+                if *name == "Johnny" {
+                    properties.insert("email".to_string(), "j@example.com".to_string());
+                }
+                Fact::EntityCreated { entity_id: *id, timestamp: Utc::now(), properties }
+            })
+            .collect();
+        db.add_fact(FactStore { facts });
+        for (source_id, rel, target_id) in [(john, RelationshipType::WorksAt, acme), (johnny, RelationshipType::LocatedAt, london)] {
+            relate(&mut db, source_id, rel, target_id);
+        }
+
+        db.merge_entities(john, johnny).unwrap();
+
+        assert!(db.get_entity(&johnny).is_none());
+        let mut targets: Vec<&str> = db.get_outgoing_neighbours(&john).iter().map(|e| e.name.as_str()).collect();
+        targets.sort();
+        assert_eq!(targets, vec!["Acme", "London"]);
+
+        let kept = db.get_entity(&john).unwrap();
+        assert_eq!(kept.properties["phone"], "555");
+        assert_eq!(kept.properties["email"], "j@example.com");
+        assert!(matches!(db.facts().last(), Some(Fact::EntityMerged { kept_id, removed_id, .. }) if *kept_id == john && *removed_id == johnny));
+
+        assert_eq!(db.merge_entities(john, johnny), Err(GraphError::EntityMissing(johnny)));
+        assert_eq!(db.merge_entities(john, john), Err(GraphError::SelfMerge(john)));
+    }
+
+    #[test]
+    fn test_persisted_merge_replays_into_merged_graph() {
+        let path = scratch_path("json");
+        let path = path.to_str().unwrap();
+
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "John", "Acme"]);
+        let (john, duplicate, acme) = (ids[0], ids[1], ids[2]);
+        relate(&mut db, duplicate, RelationshipType::WorksAt, acme);
+        db.merge_entities(john, duplicate).unwrap();
+        db.persist_facts(path).unwrap();
+
+        let loaded = GraphDb::load_from_file(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert!(loaded.load_warnings.is_empty());
+        assert_eq!(loaded.entity_count(), 2);
+        assert!(loaded.get_entity(&duplicate).is_none());
+        assert_eq!(loaded.get_outgoing_neighbours(&john)[0].id, acme);
+        assert_eq!(loaded.lifecycle_summary().entities_merged, 1);
+    }
 }
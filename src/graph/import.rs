@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::graph::fact::{Fact, FactStore};
+use crate::graph::{GraphDb, RelationshipType};
+
+/// A CSV row that couldn't be imported; the rest of the file still is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub line: usize,        // 1-based line number in the input
+    pub message: String,    // What was wrong with the row
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RowError {}
+
+/// Yields (line number, trimmed fields) for every non-blank row, skipping a header row
+/// whose first field is `header`. Fields are split on plain commas; quoting isn't supported.
+fn rows(reader: impl Read, header: &str) -> Vec<(usize, std::io::Result<Vec<String>>)> {
+    BufReader::new(reader)
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.map(|l| l.split(',').map(|f| f.trim().to_string()).collect::<Vec<_>>())))
+        .filter(|(line, fields)| match fields {
+            Ok(fields) => {
+                let blank = fields.len() == 1 && fields[0].is_empty();
+                let header_row = *line == 1 && fields[0] == header;
+                !(blank || header_row)
+            }
+            Err(_) => true,
+        })
+        .collect()
+}
+
+/// Imports entities from CSV rows of `name,type,key=value;key=value...` (the property column is optional).
+///
+/// Every good row becomes an `EntityCreated` fact, all recorded through one `add_fact` call so the
+/// event log stays authoritative. Types the graph doesn't know are kept as `EntityType::Custom`.
+/// Returns the rows that were skipped and why.
+pub fn import_entities_csv(db: &mut GraphDb, reader: impl Read) -> Vec<RowError> {
+    let mut facts = Vec::new();
+    let mut errors = Vec::new();
+    let timestamp = Utc::now();
+
+    for (line, fields) in rows(reader, "name") {
+        let fields = match fields {
+            Ok(fields) => fields,
+            Err(e) => {
+                errors.push(RowError { line, message: format!("unreadable line: {}", e) });
+                continue;
+            }
+        };
+        if fields.len() < 2 || fields.len() > 3 || fields[0].is_empty() {
+            errors.push(RowError { line, message: "expected name,type[,key=value;...]".to_string() });
+            continue;
+        }
+
+        let mut properties = BTreeMap::new();
+        properties.insert("name".to_string(), fields[0].clone());
+        properties.insert("type".to_string(), fields[1].clone());
+
+        let pairs = fields.get(2).map(String::as_str).unwrap_or("");
+        let mut malformed = None;
+        for pair in pairs.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    properties.insert(key.trim().to_string(), value.trim().to_string());
+                }
+                _ => malformed = Some(pair.to_string()),
+            }
+        }
+        if let Some(pair) = malformed {
+            errors.push(RowError { line, message: format!("property '{}' is not key=value", pair) });
+            continue;
+        }
+
+        facts.push(Fact::EntityCreated { entity_id: Uuid::new_v4(), timestamp, properties });
+    }
+
+    db.add_fact(FactStore { facts });
+    errors
+}
+
+/// Builds the `RelationshipAdded` fact for one relationship row, or explains why it can't.
+fn relationship_fact(
+    db: &GraphDb,
+    source: &str,
+    predicate: &str,
+    target: &str,
+    valid_from: &str,
+    timestamp: DateTime<Utc>,
+) -> Result<Fact, String> {
+    let resolve = |name: &str| match db.entities_named(name).as_slice() {
+        [entity] => Ok(entity.id),
+        [] => Err(format!("no entity named '{}'", name)),
+        _ => Err(format!("more than one entity named '{}'", name)),
+    };
+
+    let source_id = resolve(source)?;
+    let target_id = resolve(target)?;
+    let relationship_type = RelationshipType::from_str(predicate).map_err(|e| e.to_string())?;
+    let valid_from = valid_from.parse::<i64>().map_err(|_| format!("valid_from '{}' is not a year", valid_from))?;
+
+    Ok(Fact::RelationshipAdded {
+        source_id,
+        target_id,
+        relationship_type: relationship_type.to_string(),
+        timestamp,
+        valid_from,
+        valid_to: None,
+    })
+}
+
+/// Imports relationships from CSV rows of `source_name,predicate,target_name,valid_from`.
+///
+/// Names are matched case-insensitively and must identify exactly one entity. Predicates that aren't
+/// built-in relationship types are kept as custom types. Good rows become `RelationshipAdded` facts
+/// recorded through `add_fact`; the rest are returned as row errors.
+pub fn import_relationships_csv(db: &mut GraphDb, reader: impl Read) -> Vec<RowError> {
+    let mut facts = Vec::new();
+    let mut errors = Vec::new();
+    let timestamp = Utc::now();
+
+    for (line, fields) in rows(reader, "source_name") {
+        let fields = match fields {
+            Ok(fields) => fields,
+            Err(e) => {
+                errors.push(RowError { line, message: format!("unreadable line: {}", e) });
+                continue;
+            }
+        };
+        let [source, predicate, target, valid_from] = fields.as_slice() else {
+            errors.push(RowError { line, message: "expected source_name,predicate,target_name,valid_from".to_string() });
+            continue;
+        };
+
+        match relationship_fact(db, source, predicate, target, valid_from, timestamp) {
+            Ok(fact) => facts.push(fact),
+            Err(message) => errors.push(RowError { line, message }),
+        }
+    }
+
+    db.add_fact(FactStore { facts });
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::EntityType;
+
+    #[test]
+    fn test_import_well_formed_files() {
+        let mut db = GraphDb::new();
+        let entities = "name,type,properties\nJohn,Person,phone=555;city=London\nAcme,Company\nMystery,Alien,\n";
+        let relationships = "source_name,predicate,target_name,valid_from\njohn,WorksAt,Acme,2019\nMystery,Bribed,John,2021\n";
+
+        assert!(import_entities_csv(&mut db, entities.as_bytes()).is_empty());
+        assert!(import_relationships_csv(&mut db, relationships.as_bytes()).is_empty());
+
+        let john = db.entities_named("John")[0];
+        assert_eq!(john.entity_type, EntityType::Person);
+        assert_eq!(john.properties["phone"], "555");
+        assert_eq!(john.properties["city"], "London");
+        assert_eq!(db.entities_named("Mystery")[0].entity_type, EntityType::Custom("Alien".to_string()));
+
+        assert_eq!(db.graph.node_count(), 3);
+        assert_eq!(db.graph.edge_count(), 2);
+        // Every row went through the event log
+        assert_eq!(db.event_log.len(), 5);
+    }
+
+    #[test]
+    fn test_import_reports_malformed_row_and_keeps_the_rest() {
+        let mut db = GraphDb::new();
+        let entities = "John,Person\nAcme,Company,founded\nWidgets,Company\n";
+
+        let errors = import_entities_csv(&mut db, entities.as_bytes());
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(db.graph.node_count(), 2);
+
+        let relationships = "John,WorksAt,Widgets,2020\nJohn,WorksAt,Nobody,2020\nJohn,WorksAt,Widgets,soon\n";
+        let errors = import_relationships_csv(&mut db, relationships.as_bytes());
+
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(db.graph.edge_count(), 1);
+    }
+}
@@ -2,9 +2,11 @@ pub mod enrichment;
 pub mod entity;
 pub mod fact;
 pub mod graph;
+pub mod import;
 pub mod relationship;
 
 pub use enrichment::*;
 pub use entity::*;
 pub use graph::*;
+pub use import::*;
 pub use relationship::*;
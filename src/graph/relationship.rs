@@ -1,36 +1,94 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RelationshipType {
     WorksAt,
     LocatedAt,
+    Employs,
+    Knows,
+    OwnsAccount,
+    CommunicatedWith,
+    RelatedTo,
+    MemberOf,
+    TransactedWith,
+    Uses,
+    Custom(String), // Any other label an investigator needs, kept verbatim
 }
 
+impl RelationshipType {
+    /// Every built-in variant, i.e. all but `Custom`. Parsing recognises exactly these labels,
+    /// so a new variant that isn't listed here parses back as `Custom`.
+    pub const ALL: [RelationshipType; 10] = [
+        RelationshipType::WorksAt,
+        RelationshipType::LocatedAt,
+        RelationshipType::Employs,
+        RelationshipType::Knows,
+        RelationshipType::OwnsAccount,
+        RelationshipType::CommunicatedWith,
+        RelationshipType::RelatedTo,
+        RelationshipType::MemberOf,
+        RelationshipType::TransactedWith,
+        RelationshipType::Uses,
+    ];
+
+    /// Iterates over the built-in variants in `ALL`.
+    pub fn iter() -> impl Iterator<Item = RelationshipType> {
+        Self::ALL.into_iter()
+    }
+}
 
 impl ToString for RelationshipType {
     fn to_string(&self) -> String {
         match self {
             RelationshipType::WorksAt => "WorksAt".to_string(),
             RelationshipType::LocatedAt => "LocatedAt".to_string(),
+            RelationshipType::Employs => "Employs".to_string(),
+            RelationshipType::Knows => "Knows".to_string(),
+            RelationshipType::OwnsAccount => "OwnsAccount".to_string(),
+            RelationshipType::CommunicatedWith => "CommunicatedWith".to_string(),
+            RelationshipType::RelatedTo => "RelatedTo".to_string(),
+            RelationshipType::MemberOf => "MemberOf".to_string(),
+            RelationshipType::TransactedWith => "TransactedWith".to_string(),
+            RelationshipType::Uses => "Uses".to_string(),
+            RelationshipType::Custom(label) => label.clone(),
         }
     }
 }
 
+/// Error returned when a string can't be turned into a `RelationshipType`.
+/// Carries the offending input so callers can report it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseRelationshipError {
+    pub input: String,
+}
+
+impl fmt::Display for ParseRelationshipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid relationship type '{}': a relationship type needs a non-blank label", self.input)
+    }
+}
+
+impl std::error::Error for ParseRelationshipError {}
+
+// Labels of the variants in `RelationshipType::ALL` map to that variant; anything else becomes `Custom`.
+// Only a blank label is rejected, since it couldn't be told apart from a missing one.
 impl FromStr for RelationshipType {
-    type Err = ();
+    type Err = ParseRelationshipError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "WorksAt" => Ok(RelationshipType::WorksAt),
-            "LocatedAt" => Ok(RelationshipType::LocatedAt),
-            _ => Err(()),
+        if s.trim().is_empty() {
+            return Err(ParseRelationshipError { input: s.to_string() });
         }
+        Ok(RelationshipType::iter()
+            .find(|rel| rel.to_string() == s)
+            .unwrap_or_else(|| RelationshipType::Custom(s.to_string())))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Relationship {
     pub source_id: Uuid,
     pub target_id: Uuid,
@@ -38,3 +96,47 @@ pub struct Relationship {
     pub valid_from: i64,
     pub valid_to: Option<i64>,
 }
+
+impl Relationship {
+    /// Whether this relationship's validity window shares at least one year with `other`'s.
+    /// Windows are inclusive, and a missing `valid_to` means the relationship is still ongoing.
+    pub fn overlaps(&self, other: &Relationship) -> bool {
+        let self_end = self.valid_to.unwrap_or(i64::MAX);
+        let other_end = other.valid_to.unwrap_or(i64::MAX);
+        self.valid_from <= other_end && other.valid_from <= self_end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_variant_round_trips_through_strings() {
+        for rel in RelationshipType::iter() {
+            let label = rel.to_string();
+            assert_eq!(RelationshipType::from_str(&label), Ok(rel), "{} did not round-trip", label);
+        }
+    }
+
+    #[test]
+    fn test_unknown_labels_become_custom_and_round_trip() {
+        let rel = RelationshipType::from_str("Bribed").unwrap();
+        assert_eq!(rel, RelationshipType::Custom("Bribed".to_string()));
+        assert_eq!(rel.to_string(), "Bribed");
+        let err = RelationshipType::from_str("  ").unwrap_err();
+        assert_eq!(err, ParseRelationshipError { input: "  ".to_string() });
+        assert!(err.to_string().contains("'  '"));
+
+        let relationship = Relationship {
+            source_id: Uuid::new_v4(),
+            target_id: Uuid::new_v4(),
+            relationship_type: rel,
+            valid_from: 2020,
+            valid_to: None,
+        };
+        let json = serde_json::to_string(&relationship).unwrap();
+        let back: Relationship = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, relationship);
+    }
+}
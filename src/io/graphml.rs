@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use crate::graph::GraphDb;
+
+/// Escapes text for use in XML attribute values and element content.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl GraphDb {
+    /// Exports the whole graph as GraphML, for layout in Gephi or yEd.
+    ///
+    /// Nodes carry `entity_type` plus one data key per property name found on any entity;
+    /// edges carry `relationship_type`, `valid_from` and, when set, `valid_to`.
+    pub fn to_graphml(&self) -> String {
+        // One <key> per distinct property name, numbered in name order so output is stable
+        let property_keys: BTreeMap<&str, String> = self.graph.node_weights()
+            .flat_map(|entity| entity.properties.keys())
+            .map(String::as_str)
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, format!("p{}", i)))
+            .collect();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        xml.push_str("  <key id=\"entity_type\" for=\"node\" attr.name=\"entity_type\" attr.type=\"string\"/>\n");
+        for (name, id) in &property_keys {
+            xml.push_str(&format!(
+                "  <key id=\"{}\" for=\"node\" attr.name=\"{}\" attr.type=\"string\"/>\n",
+                id, escape(name)
+            ));
+        }
+        xml.push_str("  <key id=\"relationship_type\" for=\"edge\" attr.name=\"relationship_type\" attr.type=\"string\"/>\n");
+        xml.push_str("  <key id=\"valid_from\" for=\"edge\" attr.name=\"valid_from\" attr.type=\"long\"/>\n");
+        xml.push_str("  <key id=\"valid_to\" for=\"edge\" attr.name=\"valid_to\" attr.type=\"long\"/>\n");
+        xml.push_str("  <graph id=\"h3imd3ll\" edgedefault=\"directed\">\n");
+
+        for entity in self.graph.node_weights() {
+            xml.push_str(&format!("    <node id=\"{}\">\n", entity.id));
+            xml.push_str(&format!("      <data key=\"entity_type\">{}</data>\n", escape(&entity.entity_type.to_string())));
+            for (name, value) in &entity.properties {
+                xml.push_str(&format!("      <data key=\"{}\">{}</data>\n", property_keys[name.as_str()], escape(value)));
+            }
+            xml.push_str("    </node>\n");
+        }
+
+        for (i, rel) in self.graph.edge_weights().enumerate() {
+            xml.push_str(&format!("    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n", i, rel.source_id, rel.target_id));
+            xml.push_str(&format!(
+                "      <data key=\"relationship_type\">{}</data>\n",
+                escape(&rel.relationship_type.to_string())
+            ));
+            xml.push_str(&format!("      <data key=\"valid_from\">{}</data>\n", rel.valid_from));
+            if let Some(valid_to) = rel.valid_to {
+                xml.push_str(&format!("      <data key=\"valid_to\">{}</data>\n", valid_to));
+            }
+            xml.push_str("    </edge>\n");
+        }
+
+        xml.push_str("  </graph>\n");
+        xml.push_str("</graphml>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::graph::fact::{Fact, FactStore};
+    use crate::graph::{GraphDb, RelationshipType};
+
+    #[test]
+    fn test_graphml_has_one_element_per_node_and_edge() {
+        let mut db = GraphDb::new();
+        let (john, acme, london) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut facts: Vec<Fact> = [(john, "John & Sons", "Person"), (acme, "Acme", "Company"), (london, "London", "Place")]
+            .iter()
+            .map(|(id, name, etype)| {
+                let mut properties = BTreeMap::new();
+                properties.insert("name".to_string(), name.to_string());
+                properties.insert("type".to_string(), etype.to_string());
+                Fact::EntityCreated { entity_id: *id, timestamp: Utc::now(), properties }
+            })
+            .collect();
+        for (source_id, rel, target_id, valid_to) in [
+            (john, RelationshipType::WorksAt, acme, Some(2023)),
+            (acme, RelationshipType::LocatedAt, london, None),
+        ] {
+            facts.push(Fact::RelationshipAdded {
+                source_id,
+                target_id,
+                relationship_type: rel.to_string(),
+                timestamp: Utc::now(),
+                valid_from: 2020,
+                valid_to,
+            });
+        }
+        db.add_fact(FactStore { facts });
+
+        let xml = db.to_graphml();
+
+        // Minimal well-formedness: one root, every element opened is closed, text is escaped
+        assert!(xml.starts_with("<?xml"));
+        assert_eq!(xml.matches("<graphml").count(), 1);
+        assert_eq!(xml.matches("<node ").count(), xml.matches("</node>").count());
+        assert_eq!(xml.matches("<edge ").count(), xml.matches("</edge>").count());
+        assert_eq!(xml.matches("<data ").count(), xml.matches("</data>").count());
+        assert!(xml.contains("John &amp; Sons"));
+
+        assert_eq!(xml.matches("<node ").count(), db.graph.node_count());
+        assert_eq!(xml.matches("<edge ").count(), db.graph.edge_count());
+        assert_eq!(xml.matches("<data key=\"valid_to\">").count(), 1);
+    }
+}
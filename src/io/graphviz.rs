@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::engine::case::Case;
+use crate::graph::{Entity, GraphDb, Relationship};
+
+/// Colors cycled through for relationship types, in the order the types sort by name.
+const EDGE_PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// Escapes a label for use inside a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Assigns each distinct relationship type a palette color.
+/// Types are sorted by name first, so the same set of types always gets the same colors.
+fn edge_colors<'a>(edges: impl Iterator<Item = &'a Relationship>) -> BTreeMap<String, &'static str> {
+    let mut colors: BTreeMap<String, &'static str> = edges
+        .map(|rel| (rel.relationship_type.to_string(), ""))
+        .collect();
+    for (i, color) in colors.values_mut().enumerate() {
+        *color = EDGE_PALETTE[i % EDGE_PALETTE.len()];
+    }
+    colors
+}
+
+/// Renders nodes and edges as a DOT digraph, followed by a legend subgraph
+/// with one entry per relationship type in its edge color. Nodes are labelled by `label_key` when given.
+fn render_dot(name: &str, nodes: &[&Entity], edges: &[&Relationship], label_key: Option<&str>) -> String {
+    let colors = edge_colors(edges.iter().copied());
+    let mut dot = format!("digraph \"{}\" {{\n", escape(name));
+
+    for entity in nodes {
+        dot.push_str(&format!(
+            "    \"{}\" [label=\"{}\\n({})\"];\n",
+            entity.id,
+            escape(&entity.display_name(label_key)),
+            escape(&entity.entity_type.to_string())
+        ));
+    }
+
+    for rel in edges {
+        let rel_type = rel.relationship_type.to_string();
+        dot.push_str(&format!(
+            "    \"{}\" -> \"{}\" [label=\"{}\", color=\"{}\", fontcolor=\"{}\"];\n",
+            rel.source_id, rel.target_id, escape(&rel_type), colors[&rel_type], colors[&rel_type]
+        ));
+    }
+
+    if !colors.is_empty() {
+        dot.push_str("    subgraph cluster_legend {\n");
+        dot.push_str("        label=\"Legend\";\n");
+        dot.push_str("        node [shape=plaintext];\n");
+        for (i, (rel_type, color)) in colors.iter().enumerate() {
+            dot.push_str(&format!(
+                "        \"legend_{}\" [label=\"{}\", fontcolor=\"{}\"];\n",
+                i, escape(rel_type), color
+            ));
+        }
+        dot.push_str("    }\n");
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+impl GraphDb {
+    /// Exports the whole graph in Graphviz DOT format.
+    pub fn to_dot(&self, label_key: Option<&str>) -> String {
+        let nodes: Vec<&Entity> = self.graph.node_weights().collect();
+        let edges: Vec<&Relationship> = self.graph.edge_weights().collect();
+        render_dot("h3imd3ll", &nodes, &edges, label_key)
+    }
+}
+
+/// Exports a case in DOT format: its related entities and the edges between them.
+pub fn case_to_dot(case: &Case, db: &GraphDb, label_key: Option<&str>) -> String {
+    let nodes: Vec<&Entity> = case.related_entity_ids.iter().filter_map(|id| db.get_entity(id)).collect();
+    let in_case = |id: &Uuid| case.related_entity_ids.contains(id);
+    let edges: Vec<&Relationship> = db.graph.edge_weights()
+        .filter(|rel| in_case(&rel.source_id) && in_case(&rel.target_id))
+        .collect();
+    render_dot(&case.name, &nodes, &edges, label_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::RelationshipType;
+    use crate::test_utils::{add_entities, relate};
+
+    #[test]
+    fn test_distinct_relationship_types_get_distinct_colors_and_legend_entries() {
+        let mut db = GraphDb::new();
+        let ids = add_entities(&mut db, &["John", "Acme", "London"]);
+        let (john, acme, london) = (ids[0], ids[1], ids[2]);
+        for (source_id, rel, target_id) in [(john, RelationshipType::WorksAt, acme), (acme, RelationshipType::LocatedAt, london)] {
+            relate(&mut db, source_id, rel, target_id);
+        }
+
+        let dot = db.to_dot(None);
+
+        let edge_color = |rel: &str| {
+            dot.lines()
+                .find(|l| l.contains("->") && l.contains(&format!("label=\"{}\"", rel)))
+                .and_then(|l| l.split("color=\"").nth(1))
+                .map(|rest| rest[..7].to_string())
+                .unwrap()
+        };
+        let works_at = edge_color("WorksAt");
+        let located_at = edge_color("LocatedAt");
+        assert_ne!(works_at, located_at);
+
+        assert!(dot.contains("subgraph cluster_legend"));
+        assert!(dot.contains(&format!("[label=\"WorksAt\", fontcolor=\"{}\"]", works_at)));
+        assert!(dot.contains(&format!("[label=\"LocatedAt\", fontcolor=\"{}\"]", located_at)));
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::fmt;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::graph::fact::{Fact, FactStore};
+use crate::graph::{Entity, GraphDb, Relationship};
+
+/// Plain node/edge document: the live graph without its event log.
+/// Nodes are entities, edges are relationships, both in their serde form.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonGraph {
+    pub nodes: Vec<Entity>,
+    pub edges: Vec<Relationship>,
+}
+
+/// Reasons a node/edge document can't be imported.
+#[derive(Debug)]
+pub enum ImportError {
+    Parse(serde_json::Error),                               // Not valid JSON, or not the expected shape
+    UnknownEndpoint { source_id: Uuid, target_id: Uuid },   // An edge refers to a node missing from `nodes`
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Parse(e) => write!(f, "invalid graph document: {}", e),
+            ImportError::UnknownEndpoint { source_id, target_id } => {
+                write!(f, "edge {} -> {} refers to a node that is not in the document", source_id, target_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(e: serde_json::Error) -> Self {
+        ImportError::Parse(e)
+    }
+}
+
+impl GraphDb {
+    /// Exports the current graph as a `{ "nodes": [...], "edges": [...] }` JSON document.
+    pub fn to_json_graph(&self) -> serde_json::Result<String> {
+        let document = JsonGraph {
+            nodes: self.graph.node_weights().cloned().collect(),
+            edges: self.graph.edge_weights().cloned().collect(),
+        };
+        serde_json::to_string_pretty(&document)
+    }
+
+    /// Rebuilds a graph from a document produced by `to_json_graph`.
+    ///
+    /// The document has no history, so one `EntityCreated` per node and one `RelationshipAdded`
+    /// per edge are synthesized with the current timestamp; the new event log starts from there.
+    pub fn from_json_graph(json: &str) -> Result<GraphDb, ImportError> {
+        let document: JsonGraph = serde_json::from_str(json)?;
+        let timestamp = Utc::now();
+
+        // Reject dangling edges up front rather than silently dropping them
+        let node_ids: HashSet<Uuid> = document.nodes.iter().map(|n| n.id).collect();
+        if let Some(edge) = document.edges.iter()
+            .find(|e| !node_ids.contains(&e.source_id) || !node_ids.contains(&e.target_id))
+        {
+            return Err(ImportError::UnknownEndpoint { source_id: edge.source_id, target_id: edge.target_id });
+        }
+
+        let mut facts = Vec::new();
+        for node in document.nodes {
+            // add_fact derives name and type from the properties, so make sure they're present
+            let mut properties = node.properties;
+            properties.entry("name".to_string()).or_insert(node.name);
+            properties.entry("type".to_string()).or_insert_with(|| node.entity_type.to_string());
+            facts.push(Fact::EntityCreated { entity_id: node.id, timestamp, properties });
+        }
+        for edge in document.edges {
+            facts.push(Fact::RelationshipAdded {
+                source_id: edge.source_id,
+                target_id: edge.target_id,
+                relationship_type: edge.relationship_type.to_string(),
+                timestamp,
+                valid_from: edge.valid_from,
+                valid_to: edge.valid_to,
+            });
+        }
+
+        let mut db = GraphDb::new();
+        db.add_fact(FactStore { facts });
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::graph::RelationshipType;
+
+    fn sorted_nodes(db: &GraphDb) -> Vec<(Uuid, String, String, BTreeMap<String, String>)> {
+        let mut nodes: Vec<_> = db.graph.node_weights()
+            .map(|e| (e.id, e.name.clone(), e.entity_type.to_string(), e.properties.clone()))
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    fn sorted_edges(db: &GraphDb) -> Vec<(Uuid, Uuid, String, i64, Option<i64>)> {
+        let mut edges: Vec<_> = db.graph.edge_weights()
+            .map(|r| (r.source_id, r.target_id, r.relationship_type.to_string(), r.valid_from, r.valid_to))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn test_json_graph_round_trip() {
+        let mut db = GraphDb::new();
+        let (john, acme, london) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut facts = Vec::new();
+        for (id, name, etype) in [(john, "John", "Person"), (acme, "Acme", "Company"), (london, "London", "Place")] {
+            let mut properties = BTreeMap::new();
+            properties.insert("name".to_string(), name.to_string());
+            properties.insert("type".to_string(), etype.to_string());
+            facts.push(Fact::EntityCreated { entity_id: id, timestamp: Utc::now(), properties });
+        }
+        for (source_id, target_id, rel, valid_to) in [
+            (john, acme, RelationshipType::WorksAt, Some(2023)),
+            (acme, london, RelationshipType::LocatedAt, None),
+        ] {
+            facts.push(Fact::RelationshipAdded {
+                source_id,
+                target_id,
+                relationship_type: rel.to_string(),
+                timestamp: Utc::now(),
+                valid_from: 2019,
+                valid_to,
+            });
+        }
+        db.add_fact(FactStore { facts });
+
+        let exported = db.to_json_graph().unwrap();
+        let imported = GraphDb::from_json_graph(&exported).unwrap();
+
+        assert_eq!(sorted_nodes(&imported), sorted_nodes(&db));
+        assert_eq!(sorted_edges(&imported), sorted_edges(&db));
+    }
+
+    #[test]
+    fn test_json_graph_rejects_dangling_edge() {
+        let json = format!(
+            r#"{{"nodes": [], "edges": [{{"source_id": "{}", "target_id": "{}", "relationship_type": "WorksAt", "valid_from": 2020, "valid_to": null}}]}}"#,
+            Uuid::new_v4(),
+            Uuid::new_v4()
+        );
+        assert!(matches!(GraphDb::from_json_graph(&json), Err(ImportError::UnknownEndpoint { .. })));
+    }
+}
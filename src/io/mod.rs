@@ -0,0 +1,8 @@
+pub mod graphml;
+pub mod graphviz;
+pub mod json_loader;
+pub mod tail;
+
+pub use graphviz::case_to_dot;
+pub use json_loader::{ImportError, JsonGraph};
+pub use tail::FactTail;
@@ -0,0 +1,121 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::graph::fact::Fact;
+
+/// Cursor over a JSON Lines fact stream (one serialized `Fact` per line) that only
+/// yields facts appended since the previous poll, like `tail -f`.
+#[derive(Debug, Default)]
+pub struct FactTail {
+    offset: u64,        // Byte position up to which the source has been consumed
+    partial: Vec<u8>,   // Trailing bytes of a line still being written(may end mid-character)
+}
+
+impl FactTail {
+    /// Starts reading from the beginning of the stream.
+    pub fn new() -> Self {
+        FactTail::default()
+    }
+
+    /// Starts reading at `offset`, e.g. the current file length to skip existing facts.
+    pub fn starting_at(offset: u64) -> Self {
+        FactTail { offset, partial: Vec::new() }
+    }
+
+    /// Reads whatever was appended since the last call and returns the complete facts in it.
+    ///
+    /// A final line without a newline is held back until it is finished, so a poll that lands in the
+    /// middle of a multi-byte character just waits for the rest. If the source shrank
+    /// (e.g. it was rewritten), reading restarts from the beginning.
+    /// Lines that aren't valid facts come back as errors in their place, for the caller to report.
+    pub fn poll<R: Read + Seek>(&mut self, source: &mut R) -> io::Result<Vec<serde_json::Result<Fact>>> {
+        let len = source.seek(SeekFrom::End(0))?;
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+
+        source.seek(SeekFrom::Start(self.offset))?;
+        let read = source.read_to_end(&mut self.partial)?;
+        self.offset += read as u64;
+
+        // Everything up to the last newline is complete; keep the rest for next time
+        let complete = match self.partial.iter().rposition(|&byte| byte == b'\n') {
+            Some(end) => {
+                let rest = self.partial.split_off(end + 1);
+                std::mem::replace(&mut self.partial, rest)
+            }
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(complete.split(|&byte| byte == b'\n')
+            .map(<[u8]>::trim_ascii)
+            .filter(|l| !l.is_empty())
+            .map(serde_json::from_slice::<Fact>)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn fact_line() -> String {
+        let fact = Fact::EntityDeleted { entity_id: Uuid::new_v4(), timestamp: Utc::now() };
+        format!("{}\n", serde_json::to_string(&fact).unwrap())
+    }
+
+    #[test]
+    fn test_poll_returns_only_newly_appended_facts() {
+        let mut source = Cursor::new(Vec::new());
+        let mut tail = FactTail::new();
+
+        assert!(tail.poll(&mut source).unwrap().is_empty());
+
+        source.get_mut().extend_from_slice(fact_line().as_bytes());
+        assert_eq!(tail.poll(&mut source).unwrap().len(), 1);
+        assert!(tail.poll(&mut source).unwrap().is_empty());
+
+        // A half-written line is held back until its newline arrives
+        let line = fact_line();
+        let (head, rest) = line.split_at(10);
+        source.get_mut().extend_from_slice(head.as_bytes());
+        assert!(tail.poll(&mut source).unwrap().is_empty());
+
+        source.get_mut().extend_from_slice(rest.as_bytes());
+        source.get_mut().extend_from_slice(fact_line().as_bytes());
+        assert_eq!(tail.poll(&mut source).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_poll_waits_out_a_character_split_across_writes() {
+        let mut properties = std::collections::BTreeMap::new();
+        properties.insert("name".to_string(), "Zoë".to_string());
+        let fact = Fact::EntityCreated { entity_id: Uuid::new_v4(), timestamp: Utc::now(), properties };
+        let line = format!("{}\n", serde_json::to_string(&fact).unwrap());
+        let split = line.find('ë').unwrap() + 1; // between the two bytes of 'ë'
+
+        let mut source = Cursor::new(line.as_bytes()[..split].to_vec());
+        let mut tail = FactTail::new();
+        assert!(tail.poll(&mut source).unwrap().is_empty());
+
+        source.get_mut().extend_from_slice(&line.as_bytes()[split..]);
+        let facts = tail.poll(&mut source).unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].as_ref().unwrap(), &fact);
+    }
+
+    #[test]
+    fn test_starting_at_end_skips_existing_facts() {
+        let mut source = Cursor::new(Vec::new());
+        source.write_all(fact_line().as_bytes()).unwrap();
+
+        let mut tail = FactTail::starting_at(source.get_ref().len() as u64);
+        assert!(tail.poll(&mut source).unwrap().is_empty());
+
+        source.get_mut().extend_from_slice(fact_line().as_bytes());
+        assert_eq!(tail.poll(&mut source).unwrap().len(), 1);
+    }
+}
@@ -1 +1,7 @@
+pub mod graph;
+pub mod cli;
+pub mod engine;
+pub mod io;
 
+#[cfg(test)]
+mod test_utils;
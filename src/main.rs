@@ -1,7 +1,3 @@
-mod graph;
-mod cli;
-mod engine;
-
 fn main() {
-    cli::run_cli();
+    h3imd3ll::cli::run_cli();
 }
@@ -0,0 +1,49 @@
+// Fixtures shared by the unit tests of every module.
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::graph::fact::{Fact, FactStore};
+use crate::graph::{GraphDb, RelationshipType};
+
+// Creates one entity per name, in a single batch, and returns their ids in order
+pub fn add_entities(db: &mut GraphDb, names: &[&str]) -> Vec<Uuid> {
+    let ids: Vec<Uuid> = names.iter().map(|_| Uuid::new_v4()).collect();
+    let facts = ids
+        .iter()
+        .zip(names)
+        .map(|(id, name)| {
+            let mut properties = BTreeMap::new();
+            properties.insert("name".to_string(), name.to_string());
+            Fact::EntityCreated { entity_id: *id, timestamp: Utc::now(), properties }
+        })
+        .collect();
+    let errors = db.add_fact(FactStore { facts });
+    assert!(errors.is_empty(), "fixture entities refused: {:?}", errors);
+    ids
+}
+
+// Adds a relationship valid from 2020 with no end
+pub fn relate(db: &mut GraphDb, source_id: Uuid, rel_type: RelationshipType, target_id: Uuid) {
+    relate_during(db, source_id, rel_type, target_id, 2020, None);
+}
+
+// Adds a relationship valid over the given years
+pub fn relate_during(db: &mut GraphDb, source_id: Uuid, rel_type: RelationshipType, target_id: Uuid, valid_from: i64, valid_to: Option<i64>) {
+    let errors = db.add_fact(FactStore {
+        facts: vec![Fact::RelationshipAdded {
+            source_id,
+            target_id,
+            relationship_type: rel_type.to_string(),
+            timestamp: Utc::now(),
+            valid_from,
+            valid_to,
+        }],
+    });
+    assert!(errors.is_empty(), "fixture relationship refused: {:?}", errors);
+}
+
+// A fresh file path in the temp directory with the given extension; the test removes the file when done
+pub fn scratch_path(extension: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("h3imd3ll_test_{}.{}", Uuid::new_v4(), extension))
+}